@@ -1,12 +1,18 @@
 use std::f64::EPSILON;
 
+use crate::force::{field_acceleration, ForceLaw, post_newtonian};
 use crate::geometry::SimpleBody;
 use crate::global::*;
 
-pub fn handle_impact(universe: &mut Vec<SimpleBody>) {
+pub fn handle_impact<F: ForceLaw + ?Sized>(universe: &mut Vec<SimpleBody>, law: &F) {
     let universe_size = universe.len();
     for i in 0..universe_size {
         for j in i + 1..universe_size {
+            let feels_i = universe[i].kind.feels(universe[j].kind);
+            let feels_j = universe[j].kind.feels(universe[i].kind);
+            if !feels_i && !feels_j {
+                continue;
+            }
             let delta_x = universe[i].x - universe[j].x;
             let delta_y = universe[i].y - universe[j].y;
             let dist = delta_x * delta_x + delta_y * delta_y;
@@ -14,16 +20,38 @@ pub fn handle_impact(universe: &mut Vec<SimpleBody>) {
                 let dot = delta_x * (universe[i].vx - universe[j].vx)
                     + delta_y * (universe[i].vy - universe[j].vy);
                 let scale = 2.0 / (universe[i].m + universe[j].m) * dot / dist;
-                universe[i].vx -= scale * delta_x * universe[j].m;
-                universe[i].vy -= scale * delta_y * universe[j].m;
-                universe[j].vx += scale * delta_x * universe[i].m;
-                universe[j].vy += scale * delta_y * universe[i].m;
+                if feels_i {
+                    universe[i].vx -= scale * delta_x * universe[j].m;
+                    universe[i].vy -= scale * delta_y * universe[j].m;
+                }
+                if feels_j {
+                    universe[j].vx += scale * delta_x * universe[i].m;
+                    universe[j].vy += scale * delta_y * universe[i].m;
+                }
             } else {
-                let scale = G / dist / dist.sqrt();
-                universe[i].ax -= delta_x * scale * universe[j].m;
-                universe[i].ay -= delta_y * scale * universe[j].m;
-                universe[j].ax += delta_x * scale * universe[i].m;
-                universe[j].ay += delta_y * scale * universe[i].m;
+                let (a, b) = (universe[i].source(), universe[j].source());
+                if feels_i {
+                    let scale = law.scale(&a, &b, dist);
+                    universe[i].ax -= delta_x * scale;
+                    universe[i].ay -= delta_y * scale;
+                }
+                if feels_j {
+                    let scale = law.scale(&b, &a, dist);
+                    universe[j].ax += delta_x * scale;
+                    universe[j].ay += delta_y * scale;
+                }
+                if let Some(c) = *LIGHT_SPEED {
+                    if feels_i {
+                        let (px, py) = post_newtonian(G, c, &universe[i], &universe[j]);
+                        universe[i].ax += px;
+                        universe[i].ay += py;
+                    }
+                    if feels_j {
+                        let (px, py) = post_newtonian(G, c, &universe[j], &universe[i]);
+                        universe[j].ax += px;
+                        universe[j].ay += py;
+                    }
+                }
             }
         }
     }
@@ -31,6 +59,9 @@ pub fn handle_impact(universe: &mut Vec<SimpleBody>) {
 
 pub fn update_state(universe: &mut Vec<SimpleBody>) {
     for i in universe {
+        if !i.kind.moves() {
+            continue;
+        }
         let rw: f64 = *WIDTH / *SCALE_FACTOR;
         let rh: f64 = *HEIGHT / *SCALE_FACTOR;
         if i.vx.is_nan() {
@@ -41,10 +72,14 @@ pub fn update_state(universe: &mut Vec<SimpleBody>) {
             i.vy = 0.0;
             i.y = 0.618 * *HEIGHT / *SCALE_FACTOR;
         }
+        let (fx, fy) = field_acceleration(&FIELDS, i.x, i.y, i.vx, i.vy);
+        i.ax += fx;
+        i.ay += fy;
         i.x += i.vx * ALPHA + 0.5 * i.ax * ALPHA * ALPHA;
         i.y += i.vy * ALPHA + 0.5 * i.ay * ALPHA * ALPHA;
         i.vx += i.ax * ALPHA;
         i.vy += i.ay * ALPHA;
+        DAMPING.apply(&mut i.vx, &mut i.vy, i.m, i.x, i.y);
         if i.x + RADIUS >= rw {
             i.x = rw - RADIUS - EPSILON;
             i.vx = -0.5 * i.vx;
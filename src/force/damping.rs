@@ -0,0 +1,107 @@
+use rand::{Error, Rng, RngCore};
+
+use crate::global::{ALPHA, kind_of, SEED, step};
+
+/// Velocity dependent drag, optionally with a Langevin thermostat.
+///
+/// Friction is applied as an exact exponential decay over one step so that large coefficients
+/// stay stable; the thermostat adds Gaussian kicks that drive bodies towards `temperature`
+/// (in units where the Boltzmann constant is one). The kicks are drawn from [`KickRng`], so a run
+/// is reproducible from its seed whatever the thread scheduling.
+#[derive(Copy, Clone, Debug)]
+pub struct Damping {
+    pub linear: f64,
+    pub quadratic: f64,
+    pub langevin: f64,
+    pub temperature: f64,
+}
+
+impl Damping {
+    pub fn is_active(&self) -> bool {
+        self.linear > 0.0 || self.quadratic > 0.0 || self.langevin > 0.0
+    }
+
+    /// Damps the velocity of the body at `(x, y)` over one step.
+    pub fn apply(&self, vx: &mut f64, vy: &mut f64, mass: f64, x: f64, y: f64) {
+        if !self.is_active() {
+            return;
+        }
+        let speed = (*vx * *vx + *vy * *vy).sqrt();
+        let decay = (-(self.linear + self.quadratic * speed + self.langevin) * ALPHA).exp();
+        *vx *= decay;
+        *vy *= decay;
+        if self.langevin > 0.0 && self.temperature > 0.0 && mass > 0.0 {
+            let mut rng = KickRng::new(x, y);
+            // fluctuation-dissipation only balances the Langevin part of the friction
+            let thermal = (-self.langevin * ALPHA).exp();
+            let sigma = (self.temperature / mass * (1.0 - thermal * thermal)).sqrt();
+            *vx += sigma * gaussian(&mut rng);
+            *vy += sigma * gaussian(&mut rng);
+        }
+    }
+
+    /// Applies the damping to bodies in `from..to` of a structure-of-arrays layout.
+    pub fn apply_all(&self,
+                     x_pos: &[f64],
+                     y_pos: &[f64],
+                     vx: &mut [f64],
+                     vy: &mut [f64],
+                     mass: &[f64],
+                     from: usize,
+                     to: usize,
+    ) {
+        if !self.is_active() {
+            return;
+        }
+        for i in (from..to).filter(|&i| kind_of(i).moves()) {
+            self.apply(&mut vx[i], &mut vy[i], mass[i], x_pos[i], y_pos[i]);
+        }
+    }
+}
+
+/// Standard normal sample by the Box-Muller transform.
+pub fn gaussian<R: Rng + ?Sized>(rng: &mut R) -> f64 {
+    let u: f64 = 1.0 - rng.gen::<f64>();
+    let v: f64 = rng.gen();
+    (-2.0 * u.ln()).sqrt() * (2.0 * std::f64::consts::PI * v).cos()
+}
+
+/// SplitMix64 keyed by the run seed, the current step and a body position.
+///
+/// Identical states give identical kicks, which is what lets a restarted run continue
+/// bit-identically without saving any generator state besides the seed.
+pub struct KickRng(u64);
+
+impl KickRng {
+    pub fn new(x: f64, y: f64) -> Self {
+        let mut key = [0_u8; 32];
+        key[0..8].copy_from_slice(&(*SEED).to_le_bytes());
+        key[8..16].copy_from_slice(&step().to_le_bytes());
+        key[16..24].copy_from_slice(&x.to_bits().to_le_bytes());
+        key[24..32].copy_from_slice(&y.to_bits().to_le_bytes());
+        KickRng(seahash::hash(&key))
+    }
+}
+
+impl RngCore for KickRng {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
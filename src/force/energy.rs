@@ -0,0 +1,32 @@
+use rayon::prelude::*;
+
+use crate::force::{ForceLaw, Source};
+use crate::global::{kind_at, RADIUS};
+use crate::initial::BodyState;
+
+/// The bodies that are part of the system. Tracers keep the mass they were generated with
+/// but act on nothing, so neither their motion nor their pairs count towards the energy.
+fn sources(bodies: &[BodyState]) -> &[BodyState] {
+    let count = (0..bodies.len()).take_while(|&i| kind_at(i, bodies.len()).exerts()).count();
+    &bodies[..count]
+}
+
+/// Kinetic energy of the bodies, tracers excluded.
+pub fn kinetic_energy(bodies: &[BodyState]) -> f64 {
+    sources(bodies).iter().map(|b| 0.5 * b.m * (b.vx * b.vx + b.vy * b.vy)).sum()
+}
+
+/// Pairwise potential energy under `law`, with distances floored at the contact distance
+/// like the collisions do. Tracers and external fields are not included.
+pub fn potential_energy(bodies: &[BodyState], law: &dyn ForceLaw) -> f64 {
+    let bodies = sources(bodies);
+    let floor = 4.0 * RADIUS * RADIUS;
+    (0..bodies.len()).into_par_iter().map(|i| {
+        let a = &bodies[i];
+        let source_a = Source { mass: a.m, charge: a.q };
+        bodies[i + 1..].iter().map(|b| {
+            let dist = ((a.x - b.x) * (a.x - b.x) + (a.y - b.y) * (a.y - b.y)).max(floor);
+            law.potential(&source_a, &Source { mass: b.m, charge: b.q }, dist)
+        }).sum::<f64>()
+    }).sum()
+}
@@ -0,0 +1,119 @@
+use std::fmt::{Display, Error, Formatter};
+
+/// A background acceleration field, applied to every body after the pairwise forces.
+#[derive(Copy, Clone, Debug)]
+pub enum ExternalField {
+    /// Constant acceleration, e.g. `g` pointing down.
+    Uniform { gx: f64, gy: f64 },
+    /// A fixed point mass that never moves.
+    PointMass { x: f64, y: f64, mass: f64, g: f64 },
+    /// Logarithmic halo potential `v0^2 / 2 * ln(r^2 + rc^2)`, giving a flat rotation curve.
+    LogHalo { x: f64, y: f64, v0: f64, rc: f64 },
+    /// Frame rotating with angular velocity `omega` around `(x, y)`: centrifugal plus Coriolis.
+    Rotating { x: f64, y: f64, omega: f64 },
+}
+
+impl ExternalField {
+    pub fn acceleration(&self, px: f64, py: f64, vx: f64, vy: f64) -> (f64, f64) {
+        match *self {
+            ExternalField::Uniform { gx, gy } => (gx, gy),
+            ExternalField::PointMass { x, y, mass, g } => {
+                let delta_x = x - px;
+                let delta_y = y - py;
+                let dist = delta_x * delta_x + delta_y * delta_y;
+                if dist < crate::global::RADIUS * crate::global::RADIUS {
+                    return (0.0, 0.0);
+                }
+                let scale = g * mass / dist / dist.sqrt();
+                (delta_x * scale, delta_y * scale)
+            }
+            ExternalField::LogHalo { x, y, v0, rc } => {
+                let delta_x = px - x;
+                let delta_y = py - y;
+                let scale = -v0 * v0 / (delta_x * delta_x + delta_y * delta_y + rc * rc);
+                (delta_x * scale, delta_y * scale)
+            }
+            ExternalField::Rotating { x, y, omega } => {
+                (omega * omega * (px - x) + 2.0 * omega * vy,
+                 omega * omega * (py - y) - 2.0 * omega * vx)
+            }
+        }
+    }
+
+    /// Parses `kind:a,b,...`, e.g. `uniform:0,9.8` or `point:125,125,5000`.
+    pub fn parse(spec: &str) -> Result<ExternalField, String> {
+        let mut parts = spec.trim().splitn(2, ':');
+        let kind = parts.next().unwrap_or("");
+        let args = parts.next().unwrap_or("")
+            .split(',')
+            .filter(|x| !x.trim().is_empty())
+            .map(|x| x.trim().parse::<f64>().map_err(|_| format!("invalid number `{}` in field `{}`", x.trim(), spec)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let expect = |n: usize| if args.len() == n {
+            Ok(())
+        } else {
+            Err(format!("field `{}` expects {} parameters, got {}", kind, n, args.len()))
+        };
+        match kind {
+            "uniform" => expect(2).map(|_| ExternalField::Uniform { gx: args[0], gy: args[1] }),
+            "point" => expect(3).map(|_| ExternalField::PointMass {
+                x: args[0],
+                y: args[1],
+                mass: args[2],
+                g: crate::global::G,
+            }),
+            "halo" => expect(4).map(|_| ExternalField::LogHalo { x: args[0], y: args[1], v0: args[2], rc: args[3] }),
+            "rotating" => expect(3).map(|_| ExternalField::Rotating { x: args[0], y: args[1], omega: args[2] }),
+            _ => Err(format!("unknown field kind `{}`, expected one of uniform, point, halo, rotating", kind))
+        }
+    }
+}
+
+impl Display for ExternalField {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match *self {
+            ExternalField::Uniform { gx, gy } => write!(f, "uniform:{},{}", gx, gy),
+            ExternalField::PointMass { x, y, mass, .. } => write!(f, "point:{},{},{}", x, y, mass),
+            ExternalField::LogHalo { x, y, v0, rc } => write!(f, "halo:{},{},{},{}", x, y, v0, rc),
+            ExternalField::Rotating { x, y, omega } => write!(f, "rotating:{},{},{}", x, y, omega),
+        }
+    }
+}
+
+/// Reads one field per line; blank lines and lines starting with `#` are skipped.
+pub fn load_fields(path: &str) -> Result<Vec<ExternalField>, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    content.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim().starts_with('#'))
+        .map(|(n, line)| ExternalField::parse(line).map_err(|e| format!("{}:{}: {}", path, n + 1, e)))
+        .collect()
+}
+
+pub fn field_acceleration(fields: &[ExternalField], x: f64, y: f64, vx: f64, vy: f64) -> (f64, f64) {
+    fields.iter().fold((0.0, 0.0), |acc, f| {
+        let a = f.acceleration(x, y, vx, vy);
+        (acc.0 + a.0, acc.1 + a.1)
+    })
+}
+
+/// Adds the field acceleration to `ax`/`ay` for bodies in `from..to` of a structure-of-arrays layout.
+pub fn apply_fields(fields: &[ExternalField],
+                    x_pos: &[f64],
+                    y_pos: &[f64],
+                    vx: &[f64],
+                    vy: &[f64],
+                    ax: &mut [f64],
+                    ay: &mut [f64],
+                    from: usize,
+                    to: usize,
+) {
+    if fields.is_empty() {
+        return;
+    }
+    for i in from..to {
+        let (fx, fy) = field_acceleration(fields, x_pos[i], y_pos[i], vx[i], vy[i]);
+        ax[i] += fx;
+        ay[i] += fy;
+    }
+}
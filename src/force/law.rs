@@ -0,0 +1,65 @@
+use crate::force::{ForceLaw, Source};
+
+pub struct Newtonian {
+    pub g: f64,
+}
+
+impl ForceLaw for Newtonian {
+    fn scale(&self, _: &Source, b: &Source, dist: f64) -> f64 {
+        self.g * b.mass / dist / dist.sqrt()
+    }
+    fn potential(&self, a: &Source, b: &Source, dist: f64) -> f64 {
+        -self.g * a.mass * b.mass / dist.sqrt()
+    }
+    fn multipole(&self) -> bool {
+        true
+    }
+}
+
+/// Like charges repel, opposite charges attract.
+pub struct Coulomb {
+    pub k: f64,
+}
+
+impl ForceLaw for Coulomb {
+    fn scale(&self, a: &Source, b: &Source, dist: f64) -> f64 {
+        -self.k * a.charge * b.charge / a.mass / dist / dist.sqrt()
+    }
+    fn potential(&self, a: &Source, b: &Source, dist: f64) -> f64 {
+        self.k * a.charge * b.charge / dist.sqrt()
+    }
+}
+
+/// Repulsive inside `sigma`, weakly attractive outside, with well depth `epsilon`.
+pub struct LennardJones {
+    pub epsilon: f64,
+    pub sigma: f64,
+}
+
+impl ForceLaw for LennardJones {
+    fn scale(&self, a: &Source, _: &Source, dist: f64) -> f64 {
+        let sr6 = (self.sigma * self.sigma / dist).powi(3);
+        -24.0 * self.epsilon * (2.0 * sr6 * sr6 - sr6) / dist / a.mass
+    }
+    fn potential(&self, _: &Source, _: &Source, dist: f64) -> f64 {
+        let sr6 = (self.sigma * self.sigma / dist).powi(3);
+        4.0 * self.epsilon * (sr6 * sr6 - sr6)
+    }
+}
+
+/// Gravity screened by `exp(-r / lambda)`.
+pub struct Yukawa {
+    pub g: f64,
+    pub lambda: f64,
+}
+
+impl ForceLaw for Yukawa {
+    fn scale(&self, _: &Source, b: &Source, dist: f64) -> f64 {
+        let r = dist.sqrt();
+        self.g * b.mass * (-r / self.lambda).exp() * (1.0 + r / self.lambda) / dist / r
+    }
+    fn potential(&self, a: &Source, b: &Source, dist: f64) -> f64 {
+        let r = dist.sqrt();
+        -self.g * a.mass * b.mass * (-r / self.lambda).exp() / r
+    }
+}
@@ -0,0 +1,43 @@
+pub use damping::*;
+pub use energy::*;
+pub use field::*;
+pub use law::*;
+pub use post_newtonian::*;
+
+mod damping;
+mod energy;
+mod field;
+mod law;
+mod post_newtonian;
+
+/// The per-body quantities a force law may couple to.
+#[derive(Copy, Clone, Debug)]
+pub struct Source {
+    pub mass: f64,
+    pub charge: f64,
+}
+
+/// A pairwise central force between two bodies.
+pub trait ForceLaw: Send + Sync {
+    /// Returns `s` such that the acceleration of `a` caused by `b` is `s * (b - a)`,
+    /// where `dist` is the squared distance between them.
+    fn scale(&self, a: &Source, b: &Source, dist: f64) -> f64;
+
+    /// Potential energy of the pair at squared distance `dist`. Laws that cannot tell,
+    /// like plain closures, return NaN and leave the total energy unknown.
+    fn potential(&self, _a: &Source, _b: &Source, _dist: f64) -> f64 {
+        std::f64::NAN
+    }
+
+    /// Whether a far group of bodies can be replaced by its total mass at the mass center.
+    /// The quad tree engines only accept laws for which this holds.
+    fn multipole(&self) -> bool {
+        false
+    }
+}
+
+impl<F> ForceLaw for F where F: Fn(&Source, &Source, f64) -> f64 + Send + Sync {
+    fn scale(&self, a: &Source, b: &Source, dist: f64) -> f64 {
+        self(a, b, dist)
+    }
+}
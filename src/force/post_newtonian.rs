@@ -0,0 +1,128 @@
+use crate::geometry::SimpleBody;
+
+/// First post-Newtonian correction to the acceleration of `a` caused by `b`.
+///
+/// This is the harmonic gauge two-body 1PN relative acceleration, shared between the pair in
+/// proportion to the partner's mass. It is exact for an isolated binary and a pairwise
+/// approximation otherwise; it assumes the bodies interact through Newtonian gravity with
+/// constant `g`, and `c` is the speed of light in simulation units.
+pub fn post_newtonian(g: f64, c: f64, a: &SimpleBody, b: &SimpleBody) -> (f64, f64) {
+    let rx = a.x - b.x;
+    let ry = a.y - b.y;
+    let vx = a.vx - b.vx;
+    let vy = a.vy - b.vy;
+    let r = (rx * rx + ry * ry).sqrt();
+    let nx = rx / r;
+    let ny = ry / r;
+    let total = a.m + b.m;
+    let eta = a.m * b.m / (total * total);
+    let gm = g * total;
+    let v2 = vx * vx + vy * vy;
+    let rdot = nx * vx + ny * vy;
+    let c2 = c * c;
+    let radial = (-1.5 * eta * rdot * rdot + (1.0 + 3.0 * eta) * v2 - (4.0 + 2.0 * eta) * gm / r) / c2;
+    let tangential = (2.0 * eta - 4.0) * rdot / c2;
+    let scale = -gm / (r * r) * b.m / total;
+    (scale * (radial * nx + tangential * vx), scale * (radial * ny + tangential * vy))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::geometry::{Kind, SimpleBody};
+
+    use super::post_newtonian;
+
+    const G: f64 = 1.0;
+    const C: f64 = 100.0;
+
+    fn body(x: f64, y: f64, vx: f64, vy: f64, m: f64) -> SimpleBody {
+        SimpleBody { x, y, m, q: 0.0, kind: Kind::Dynamic, vx, vy, ax: 0.0, ay: 0.0 }
+    }
+
+    fn copy(b: &SimpleBody) -> SimpleBody {
+        body(b.x, b.y, b.vx, b.vy, b.m)
+    }
+
+    /// Newtonian plus 1PN acceleration of both bodies.
+    fn accelerations(bodies: &[SimpleBody; 2]) -> [(f64, f64); 2] {
+        let mut result = [(0.0, 0.0); 2];
+        for (i, j) in [(0, 1), (1, 0)].iter().cloned() {
+            let (a, b) = (&bodies[i], &bodies[j]);
+            let (dx, dy) = (b.x - a.x, b.y - a.y);
+            let r = (dx * dx + dy * dy).sqrt();
+            let (px, py) = post_newtonian(G, C, a, b);
+            result[i] = (G * b.m * dx / (r * r * r) + px, G * b.m * dy / (r * r * r) + py);
+        }
+        result
+    }
+
+    /// One classic Runge-Kutta step, as the 1PN term depends on the velocities.
+    fn rk4(bodies: &mut [SimpleBody; 2], dt: f64) {
+        let shifted = |base: &[SimpleBody; 2], k: &[(f64, f64, f64, f64); 2], h: f64| {
+            let mut moved = [copy(&base[0]), copy(&base[1])];
+            for (b, k) in moved.iter_mut().zip(k) {
+                b.x += h * k.0;
+                b.y += h * k.1;
+                b.vx += h * k.2;
+                b.vy += h * k.3;
+            }
+            moved
+        };
+        let derive = |state: &[SimpleBody; 2]| {
+            let a = accelerations(state);
+            [(state[0].vx, state[0].vy, a[0].0, a[0].1), (state[1].vx, state[1].vy, a[1].0, a[1].1)]
+        };
+        let k1 = derive(bodies);
+        let k2 = derive(&shifted(bodies, &k1, dt / 2.0));
+        let k3 = derive(&shifted(bodies, &k2, dt / 2.0));
+        let k4 = derive(&shifted(bodies, &k3, dt));
+        for (n, b) in bodies.iter_mut().enumerate() {
+            b.x += dt / 6.0 * (k1[n].0 + 2.0 * k2[n].0 + 2.0 * k3[n].0 + k4[n].0);
+            b.y += dt / 6.0 * (k1[n].1 + 2.0 * k2[n].1 + 2.0 * k3[n].1 + k4[n].1);
+            b.vx += dt / 6.0 * (k1[n].2 + 2.0 * k2[n].2 + 2.0 * k3[n].2 + k4[n].2);
+            b.vy += dt / 6.0 * (k1[n].3 + 2.0 * k2[n].3 + 2.0 * k3[n].3 + k4[n].3);
+        }
+    }
+
+    /// Direction of the Runge-Lenz vector of the relative orbit, which points at periapsis.
+    fn periapsis_angle(bodies: &[SimpleBody; 2]) -> f64 {
+        let gm = G * (bodies[0].m + bodies[1].m);
+        let (rx, ry) = (bodies[1].x - bodies[0].x, bodies[1].y - bodies[0].y);
+        let (vx, vy) = (bodies[1].vx - bodies[0].vx, bodies[1].vy - bodies[0].vy);
+        let r = (rx * rx + ry * ry).sqrt();
+        let l = rx * vy - ry * vx;
+        (-vx * l - gm * ry / r).atan2(vy * l - gm * rx / r)
+    }
+
+    #[test]
+    fn perihelion_precession() {
+        let (big, small) = (1.0, 1e-3);
+        let (a, e) = (1.0, 0.5);
+        let gm = G * (big + small);
+        // start at periapsis, in the centre of mass frame
+        let (r, v) = (a * (1.0 - e), (gm * (1.0 + e) / (a * (1.0 - e))).sqrt());
+        let mut bodies = [
+            body(-r * small / (big + small), 0.0, 0.0, -v * small / (big + small), big),
+            body(r * big / (big + small), 0.0, 0.0, v * big / (big + small), small),
+        ];
+        let radial = |b: &[SimpleBody; 2]| (b[1].x - b[0].x) * (b[1].vx - b[0].vx) + (b[1].y - b[0].y) * (b[1].vy - b[0].vy);
+        let orbits = 5;
+        let dt = 1e-3;
+        let start = periapsis_angle(&bodies);
+        let mut passes = Vec::new();
+        let mut last = radial(&bodies);
+        while passes.len() < orbits {
+            rk4(&mut bodies, dt);
+            let now = radial(&bodies);
+            if last < 0.0 && now >= 0.0 {
+                passes.push(periapsis_angle(&bodies));
+            }
+            last = now;
+        }
+        let measured = (passes[orbits - 1] - start) / orbits as f64;
+        let expected = 6.0 * PI * gm / (C * C * a * (1.0 - e * e));
+        assert!((measured - expected).abs() < 0.02 * expected, "measured {} expected {}", measured, expected);
+    }
+}
@@ -3,13 +3,39 @@ use std::sync::Arc;
 
 use nalgebra::Vector2;
 
+use crate::force::{field_acceleration, Source};
 use crate::geometry::Point;
 use crate::global::*;
+use crate::initial::BodyState;
 use crate::quad_tree::node::*;
 
 
+/// How a body takes part in the pairwise interaction.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Kind {
+    Dynamic,
+    /// Exerts force but never moves.
+    Pinned,
+    /// Feels force but exerts none, and is never inserted into the quad tree.
+    Tracer,
+}
+
+impl Kind {
+    pub fn moves(self) -> bool {
+        self != Kind::Pinned
+    }
+    pub fn exerts(self) -> bool {
+        self != Kind::Tracer
+    }
+    /// Whether a body of this kind needs the force exerted by a body of kind `source`.
+    pub fn feels(self, source: Kind) -> bool {
+        self.moves() && source.exerts()
+    }
+}
+
 pub struct Body {
     node: Arc<QuadNode>,
+    pub kind: Kind,
     pub position: Point,
     pub velocity: Vector2<f64>,
     pub acceleration: Vector2<f64>,
@@ -17,39 +43,83 @@ pub struct Body {
 
 impl Body {
     pub fn make_ready(&mut self) {
-        self.node = make_ready(self.position.clone(), self.node.clone())
+        if self.kind.exerts() {
+            self.node = make_ready(self.position.clone(), self.node.clone())
+        }
     }
     pub fn collision_detect(&mut self) {
+        if self.kind != Kind::Dynamic {
+            return;
+        }
         let impact = collision_detect(&self.position, self.node.clone());
         self.velocity.x += impact.x;
         self.velocity.y += impact.y;
     }
     pub fn gravity_impact(&mut self, root: Arc<QuadNode>) {
+        if !self.kind.moves() {
+            return;
+        }
         let impact = get_impact(&self.position, root);
-        self.acceleration.x = impact.0 / self.position.mass;
-        self.acceleration.y = impact.1 / self.position.mass;
+        self.acceleration.x = impact.0;
+        self.acceleration.y = impact.1;
+        let (fx, fy) = field_acceleration(&FIELDS, self.position.x, self.position.y, self.velocity.x, self.velocity.y);
+        self.acceleration.x += fx;
+        self.acceleration.y += fy;
     }
     pub fn update_position(&mut self) {
+        if !self.kind.moves() {
+            return;
+        }
         self.position.x += self.velocity.x * ALPHA;
         self.position.y += self.velocity.y * ALPHA;
     }
     pub fn update_velocity(&mut self) {
+        if !self.kind.moves() {
+            return;
+        }
         self.velocity.x += self.acceleration.x * ALPHA;
         self.velocity.y += self.acceleration.y * ALPHA;
+        let (mut vx, mut vy) = (self.velocity.x, self.velocity.y);
+        DAMPING.apply(&mut vx, &mut vy, self.position.mass, self.position.x, self.position.y);
+        self.velocity.x = vx;
+        self.velocity.y = vy;
     }
     pub fn geometric(&self) -> sdl2::rect::Point {
         sdl2::rect::Point::new(self.position.x as i32, self.position.y as i32)
     }
-    pub fn new(x: f64, y: f64, mass: f64, root: Arc<QuadNode>) -> Body {
+    pub fn new(x: f64, y: f64, mass: f64, kind: Kind, root: Arc<QuadNode>) -> Body {
         let position = Point { x, y, mass };
         Body {
-            node: insert(root, position.clone()),
+            node: if kind.exerts() { insert(root, position.clone()) } else { root },
+            kind,
             position,
             velocity: Vector2::new(0.0, 0.0),
             acceleration: Vector2::new(0.0, 0.0),
         }
     }
+    pub fn from_state(state: &BodyState, kind: Kind, root: Arc<QuadNode>) -> Body {
+        let mut body = Body::new(state.x, state.y, state.m, kind, root);
+        body.velocity = Vector2::new(state.vx, state.vy);
+        body
+    }
+    pub fn state(&self) -> BodyState {
+        BodyState {
+            x: self.position.x,
+            y: self.position.y,
+            vx: self.velocity.x,
+            vy: self.velocity.y,
+            m: self.position.mass,
+            q: 0.0,
+        }
+    }
+    pub fn carried(&self) -> (f64, f64) {
+        (self.acceleration.x, self.acceleration.y)
+    }
     pub fn reinsert(&mut self, root: Arc<QuadNode>) {
+        if !self.kind.exerts() {
+            self.node = root;
+            return;
+        }
         self.node = insert(root, self.position.clone());
     }
     pub fn check_boundary(&mut self) {
@@ -74,10 +144,22 @@ impl Body {
     }
 }
 
+/// Gives the bodies the accelerations saved by the `--restart` checkpoint, which their first
+/// step kicks them with.
+pub fn restore_accelerations(bodies: &mut [Body]) {
+    if let Some(checkpoint) = RESTART.as_ref() {
+        for (body, &(ax, ay)) in bodies.iter_mut().zip(&checkpoint.accelerations) {
+            body.acceleration = Vector2::new(ax, ay);
+        }
+    }
+}
+
 pub struct SimpleBody {
     pub x: f64,
     pub y: f64,
     pub m: f64,
+    pub q: f64,
+    pub kind: Kind,
     pub vx: f64,
     pub vy: f64,
     pub ax: f64,
@@ -85,6 +167,12 @@ pub struct SimpleBody {
 }
 
 impl SimpleBody {
+    pub fn state(&self) -> BodyState {
+        BodyState { x: self.x, y: self.y, vx: self.vx, vy: self.vy, m: self.m, q: self.q }
+    }
+    pub fn source(&self) -> Source {
+        Source { mass: self.m, charge: self.q }
+    }
     pub fn to_sdl(&self) -> sdl2::rect::Point {
         sdl2::rect::Point::new(self.x as i32, self.y as i32)
     }
@@ -1,34 +1,47 @@
-use std::time::SystemTime;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
 
 use clap::*;
 use hashbrown::HashMap;
 use lazy_static;
 use mpi::environment::*;
 use mpi::topology::{Process, SystemCommunicator};
-use mpi::traits::Communicator;
+use mpi::traits::{Communicator, Root};
 use nalgebra::Vector2;
-use parking_lot::RwLock;
-use crate::geometry::Point;
+use parking_lot::{Mutex, RwLock};
+use crate::force::*;
+use crate::geometry::{Kind, Point};
+use crate::initial::{BodyState, Component, parse_spec};
+use crate::io::{Checkpoint, Checkpoints, Diagnostics, Format, load_checkpoint, load_input, Snapshots, TrajectoryWriter};
+use crate::render::{BodyStyle, ColorBy, create_export, Export, FrameFormat, Frames, TreeColor, Vectors};
+use crate::viewer::{Backend, Stepping, Tracking};
 
 lazy_static! {
     pub static ref VMAP : RwLock<HashMap<Point, Vector2<f64>>> = RwLock::new(HashMap::new());
 
-    pub static ref UNIVERSE : Universe = initialize().unwrap();
+    /// The window steps the engine on its own thread, so MPI has to accept calls from threads
+    /// other than the main one, one at a time. The level the library provides may be lower,
+    /// see [`mpi_threaded`].
+    pub static ref UNIVERSE : (Universe, Threading) = initialize_with_threading(Threading::Serialized).unwrap();
 
-    pub static ref WORLD : SystemCommunicator = UNIVERSE.world();
+    pub static ref WORLD : SystemCommunicator = UNIVERSE.0.world();
 
     static ref ENGINES : Vec<&'static str> =
         vec!["tree", "openmp", "pthread", "mpi_normal", "mpi_openmp", "brute_force", "rayon", "rayon_tree"];
 
     static ref MODES : Vec<&'static str> =
-        vec!["benchmark", "display"];
+        vec!["benchmark", "display", "record", "replay"];
+
+    static ref FORCES : Vec<&'static str> =
+        vec!["newton", "coulomb", "lennard_jones", "yukawa"];
 
     pub static ref MATCHES : Option<ArgMatches<'static>> = {
         let result = App::new("Assignment-3")
         .version("2019Full-A3")
         .author("Schrodinger Zhu <i@zhuyi.fan>")
         .arg(Arg::with_name("engine")
-            .short("e").value_name("ENGINE").help("render engine").required(true)
+            .short("e").value_name("ENGINE").help("render engine; bodies can be spawned and deleted with the mouse \
+                in the window on every engine but mpi_normal and mpi_openmp").required(true)
             .possible_values(ENGINES.as_slice()))
         .arg(Arg::with_name("width")
             .short("w").value_name("WIDTH").help("canvas width").default_value("1000"))
@@ -41,9 +54,126 @@ lazy_static! {
         .arg(Arg::with_name("thread").help("thread number (for openmp/pthread), must be greater than 0, otherwise reset to 6")
             .short("t").default_value("6"))
         .arg(Arg::with_name("mode").value_name("MODE")
-            .short("m").help("running mode").possible_values(MODES.as_slice()).default_value("display"))
+            .short("m").help("running mode; benchmark and record run --steps steps without a window, \
+                replay plays back --trajectory and ignores the engine").possible_values(MODES.as_slice()).default_value("display"))
         .arg(Arg::with_name("fps").value_name("FPS_FLAG")
             .short("f").help("whether to show fps").possible_values(&["yes", "no"]).default_value("no"))
+        .arg(Arg::with_name("display").value_name("DISPLAY")
+            .long("display").help("where display and replay mode draw: an SDL window, or braille dots in the terminal \
+                for use without X11").possible_values(&["sdl", "terminal"]).default_value("sdl"))
+        .arg(Arg::with_name("stepping").value_name("STEPPING")
+            .long("stepping").help("the window runs the engine on its own thread, either for the steps of the playback speed \
+                once per frame or as fast as possible").possible_values(&["frame", "free"]).default_value("frame"))
+        .arg(Arg::with_name("max_fps").value_name("FPS")
+            .long("max-fps").help("frame rate cap of the window, 0 for none").default_value("60"))
+        .arg(Arg::with_name("hud").value_name("HUD_FLAG")
+            .long("hud").help("whether to overlay step, time, fps, timings and energy on the window; H toggles it")
+            .possible_values(&["yes", "no"]).default_value("no"))
+        .arg(Arg::with_name("track").value_name("TRACKING")
+            .long("track").help("what the window camera follows: the centre of mass, the most massive body, the \
+                selected body, or every body zoomed to fit; F switches it")
+            .possible_values(&["off", "com", "heaviest", "selected", "fit"]).default_value("off"))
+        .arg(Arg::with_name("steps").value_name("STEPS")
+            .long("steps").help("number of steps in benchmark and record mode, defaults to 1 and 1000"))
+        .arg(Arg::with_name("trajectory").value_name("FILE")
+            .long("trajectory").help("trajectory file to record positions into, or to replay")
+            .required_ifs(&[("mode", "record"), ("mode", "replay")]))
+        .arg(Arg::with_name("trajectory_every").value_name("STEPS")
+            .long("trajectory-every").help("steps between recorded trajectory frames").default_value("1"))
+        .arg(Arg::with_name("replay_speed").value_name("FRAMES")
+            .long("replay-speed").help("frames advanced per displayed frame in replay mode, negative to play backwards")
+            .default_value("1").allow_hyphen_values(true))
+        .arg(Arg::with_name("input").value_name("FILE")
+            .long("input").help("read the bodies from a csv or binary body file instead of generating them"))
+        .arg(Arg::with_name("output").value_name("DIR")
+            .long("output").help("write snapshots of the body state into this directory"))
+        .arg(Arg::with_name("every").value_name("STEPS")
+            .long("every").help("steps between snapshots").default_value("1"))
+        .arg(Arg::with_name("output_format").value_name("FORMAT")
+            .long("output-format").help("snapshot file formats, comma separated; npy stores a (6, n) array of x, y, vx, vy, m, q \
+                and npz one array per column plus step and time")
+            .possible_values(&["csv", "binary", "npy", "npz", "both"]).default_value("csv")
+            .multiple(true).use_delimiter(true))
+        .arg(Arg::with_name("diagnostics").value_name("FILE")
+            .long("diagnostics").help("write a (5, k) npy array of step, time, kinetic, potential and total energy \
+                into this file, one column per sample"))
+        .arg(Arg::with_name("diagnostics_every").value_name("STEPS")
+            .long("diagnostics-every").help("steps between diagnostics samples").default_value("10"))
+        .arg(Arg::with_name("frames").value_name("DIR")
+            .long("frames").help("render frames off-screen into this directory, also without a display"))
+        .arg(Arg::with_name("frame_every").value_name("STEPS")
+            .long("frame-every").help("steps between rendered frames").default_value("1"))
+        .arg(Arg::with_name("frame_format").value_name("FORMAT")
+            .long("frame-format").help("rendered frame file format").possible_values(&["png", "ppm"]).default_value("png"))
+        .arg(Arg::with_name("export").value_name("FORMAT")
+            .long("export").help("export the run as an animated gif or as an svg of the last exported step")
+            .possible_values(&["gif", "svg"]))
+        .arg(Arg::with_name("export_path").value_name("FILE")
+            .long("export-path").help("file to export into, defaults to run.gif or run.svg"))
+        .arg(Arg::with_name("export_every").value_name("STEPS")
+            .long("export-every").help("steps between exported frames").default_value("10"))
+        .arg(Arg::with_name("export_color").value_name("QUANTITY")
+            .long("export-color").help("quantity that colours the svg bodies").possible_values(&["mass", "speed"]).default_value("mass"))
+        .arg(Arg::with_name("color_by").value_name("QUANTITY")
+            .long("color-by").help("quantity that colours the bodies; cycle is the single colour changing every frame, \
+                owner the thread or rank updating each body. C switches it in the window")
+            .possible_values(&["cycle", "speed", "mass", "energy", "density", "owner"]).default_value("cycle"))
+        .arg(Arg::with_name("style").value_name("STYLE")
+            .long("style").help("draw bodies as squares of one simulation unit or as circles of their radius; B toggles it in the window")
+            .possible_values(&["points", "circles"]).default_value("points"))
+        .arg(Arg::with_name("trails").value_name("LENGTH")
+            .long("trails").help("draw fading trails of the last LENGTH displayed positions of every body, 0 to start without; \
+                T toggles them").default_value("0"))
+        .arg(Arg::with_name("vectors").value_name("VECTOR")
+            .long("vectors").help("draw velocity or acceleration arrows; V switches them")
+            .possible_values(&["none", "velocity", "acceleration"]).default_value("none"))
+        .arg(Arg::with_name("quad_tree").value_name("COLOR")
+            .long("quad-tree").help("outline the quad tree of the tree, pthread and rayon_tree engines, coloured plainly, \
+                by depth or by body count; Q switches it, clicking a body selects it and O toggles highlighting the nodes \
+                it opens").possible_values(&["none", "plain", "depth", "count"]).default_value("none"))
+        .arg(Arg::with_name("checkpoint").value_name("FILE")
+            .long("checkpoint").help("periodically save the full simulation state into this file"))
+        .arg(Arg::with_name("checkpoint_every").value_name("STEPS")
+            .long("checkpoint-every").help("steps between checkpoints").default_value("1000"))
+        .arg(Arg::with_name("restart").value_name("FILE")
+            .long("restart").help("continue the run saved in this checkpoint file, bit for bit as if it had never stopped")
+            .conflicts_with("input"))
+        .arg(Arg::with_name("seed").value_name("SEED")
+            .long("seed").help("seed of the initial conditions and the langevin thermostat, random by default"))
+        .arg(Arg::with_name("ic").value_name("DESCRIPTION")
+            .long("ic").help("initial conditions as `;`-separated components `KIND [key=value]...`, where KIND is one of \
+                uniform, plummer, king, hernquist, disk, galaxies, kepler, square, hex, ring, shell, blob or image; \
+                every component accepts n, mass and dispersion, and all but uniform also x and y").default_value("uniform"))
+        .arg(Arg::with_name("ic_radius").value_name("RADIUS")
+            .long("ic-radius").help("scale radius of clustered initial conditions, defaults to a tenth of the domain"))
+        .arg(Arg::with_name("king_w0").value_name("W0")
+            .long("king-w0").help("central potential of the king model").default_value("6"))
+        .arg(Arg::with_name("central_mass").value_name("MASS")
+            .long("central-mass").help("mass of the central body of disk galaxies and planetary systems"))
+        .arg(Arg::with_name("planets").value_name("FILE")
+            .long("planets").help("orbital elements for the kepler initial conditions: mass, a, e, periapsis and mean anomaly in degrees"))
+        .arg(Arg::with_name("force").value_name("LAW")
+            .long("force").help("pairwise force law").possible_values(FORCES.as_slice()).default_value("newton"))
+        .arg(Arg::with_name("field").value_name("FIELD")
+            .long("field").help("external field, e.g. uniform:0,9.8 point:X,Y,M halo:X,Y,V0,RC rotating:X,Y,OMEGA")
+            .multiple(true).number_of_values(1))
+        .arg(Arg::with_name("field_file").value_name("FILE")
+            .long("field-file").help("file listing one external field per line"))
+        .arg(Arg::with_name("light_speed").value_name("C")
+            .long("light-speed").help("enable 1PN corrections with this speed of light; needs --force newton and the \
+                brute_force or rayon engine"))
+        .arg(Arg::with_name("pinned").value_name("NUM")
+            .long("pinned").help("number of pinned bodies, taken from the front").default_value("0"))
+        .arg(Arg::with_name("tracers").value_name("NUM")
+            .long("tracers").help("number of massless tracer bodies, taken from the back").default_value("0"))
+        .arg(Arg::with_name("drag").value_name("COEFFICIENT")
+            .long("drag").help("linear drag coefficient").default_value("0"))
+        .arg(Arg::with_name("quadratic_drag").value_name("COEFFICIENT")
+            .long("quadratic-drag").help("quadratic drag coefficient").default_value("0"))
+        .arg(Arg::with_name("langevin").value_name("FRICTION")
+            .long("langevin").help("langevin thermostat friction, 0 to disable").default_value("0"))
+        .arg(Arg::with_name("temperature").value_name("TEMPERATURE")
+            .long("temperature").help("langevin thermostat target temperature").default_value("0"))
         .get_matches_safe();
         match result {
             Ok(x) => Some(x),
@@ -71,26 +201,323 @@ lazy_static! {
         _ => 1.0
     };
 
-    pub static ref SIZE : usize = match MATCHES.as_ref().and_then(|m| m.value_of("number").and_then(|x|x.parse::<usize>().ok())) {
-        Some(w)  => w,
-        _ => 50
+    /// Only the root process reads the input file; the others learn the body count from it.
+    pub static ref INPUT : Option<Vec<BodyState>> = MATCHES.as_ref().and_then(|m| m.value_of("input")).map(|path| {
+        match load_input(path) {
+            Ok(x) => x,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    });
+
+    /// Like [`INPUT`], the checkpoint is only read by the root process.
+    pub static ref RESTART : Option<Checkpoint> = MATCHES.as_ref().and_then(|m| m.value_of("restart"))
+        .filter(|_| WORLD.rank() == ROOT)
+        .map(|path| {
+            match load_checkpoint(path) {
+                Ok(x) => x,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            }
+        });
+
+    pub static ref SIZE : usize = if MATCHES.as_ref().and_then(|m| m.value_of("input").or(m.value_of("restart"))).is_some() {
+        let mut size = if WORLD.rank() == ROOT {
+            RESTART.as_ref().map(|x| x.bodies.len()).or(INPUT.as_ref().map(|x| x.len())).unwrap_or(0)
+        } else { 0 };
+        WORLD.process_at_rank(ROOT).broadcast_into(&mut size);
+        size
+    } else {
+        match MATCHES.as_ref().and_then(|m| m.value_of("number").and_then(|x|x.parse::<usize>().ok())) {
+            Some(w)  => w,
+            _ => 50
+        }
+    };
+
+    pub static ref LIGHT_SPEED : Option<f64> = match MATCHES.as_ref().and_then(|m| m.value_of("light_speed").and_then(|x|x.parse::<f64>().ok())) {
+        Some(w) if w > 0.0 => Some(w),
+        _ => None
+    };
+
+    pub static ref PINNED : usize = match MATCHES.as_ref().and_then(|m| m.value_of("pinned").and_then(|x|x.parse::<usize>().ok())) {
+        Some(w) => w.min(*SIZE),
+        _ => 0
+    };
+
+    pub static ref TRACERS : usize = match MATCHES.as_ref().and_then(|m| m.value_of("tracers").and_then(|x|x.parse::<usize>().ok())) {
+        Some(w) => w.min(*SIZE - *PINNED),
+        _ => 0
+    };
+
+    pub static ref OUTPUT : Option<Snapshots> = MATCHES.as_ref().and_then(|m| m.value_of("output")).map(|dir| {
+        if let Err(e) = std::fs::create_dir_all(dir) {
+            eprintln!("unable to create output directory {}: {}", dir, e);
+            std::process::exit(1);
+        }
+        Snapshots {
+            dir: dir.into(),
+            every: match MATCHES.as_ref().and_then(|m| m.value_of("every").and_then(|x|x.parse::<u64>().ok())) {
+                Some(w) if w > 0 => w,
+                _ => 1
+            },
+            formats: {
+                let mut formats = Vec::new();
+                for name in MATCHES.as_ref().and_then(|m| m.values_of("output_format")).into_iter().flatten() {
+                    let names = if name == "both" { vec!["csv", "binary"] } else { vec![name] };
+                    for format in names.into_iter().filter_map(Format::parse) {
+                        if !formats.contains(&format) {
+                            formats.push(format);
+                        }
+                    }
+                }
+                formats
+            },
+        }
+    });
+
+    pub static ref FRAMES : Option<Frames> = MATCHES.as_ref().and_then(|m| m.value_of("frames")).map(|dir| {
+        if let Err(e) = std::fs::create_dir_all(dir) {
+            eprintln!("unable to create frame directory {}: {}", dir, e);
+            std::process::exit(1);
+        }
+        Frames {
+            dir: dir.into(),
+            every: match MATCHES.as_ref().and_then(|m| m.value_of("frame_every").and_then(|x|x.parse::<u64>().ok())) {
+                Some(w) if w > 0 => w,
+                _ => 1
+            },
+            format: match MATCHES.as_ref().and_then(|m| m.value_of("frame_format")) {
+                Some("ppm") => FrameFormat::Ppm,
+                _ => FrameFormat::Png
+            },
+        }
+    });
+
+    pub static ref COLOR_BY : ColorBy = MATCHES.as_ref()
+        .and_then(|m| m.value_of("color_by"))
+        .and_then(ColorBy::parse)
+        .unwrap_or(ColorBy::Cycle);
+
+    pub static ref STYLE : BodyStyle = match MATCHES.as_ref().and_then(|m| m.value_of("style")) {
+        Some("circles") => BodyStyle::Circles,
+        _ => BodyStyle::Points
+    };
+
+    pub static ref TRAILS : usize = MATCHES.as_ref()
+        .and_then(|m| m.value_of("trails").and_then(|x|x.parse::<usize>().ok()))
+        .unwrap_or(0);
+
+    pub static ref VECTORS : Vectors = match MATCHES.as_ref().and_then(|m| m.value_of("vectors")) {
+        Some("velocity") => Vectors::Velocity,
+        Some("acceleration") => Vectors::Acceleration,
+        _ => Vectors::None
+    };
+
+    pub static ref QUAD_TREE : Option<TreeColor> = MATCHES.as_ref()
+        .and_then(|m| m.value_of("quad_tree"))
+        .and_then(TreeColor::parse);
+
+    /// Only the root process samples diagnostics, from the bodies it records.
+    pub static ref DIAGNOSTICS_OUTPUT : Option<Mutex<Diagnostics>> = MATCHES.as_ref()
+        .and_then(|m| m.value_of("diagnostics"))
+        .filter(|_| WORLD.rank() == ROOT)
+        .map(|path| {
+            let every = match MATCHES.as_ref().and_then(|m| m.value_of("diagnostics_every").and_then(|x|x.parse::<u64>().ok())) {
+                Some(w) if w > 0 => w,
+                _ => 10
+            };
+            Mutex::new(Diagnostics::new(path.into(), every))
+        });
+
+    /// Only the root process exports, like it is the only one that records.
+    pub static ref EXPORT : Option<Mutex<Export>> = MATCHES.as_ref()
+        .and_then(|m| m.value_of("export"))
+        .filter(|_| WORLD.rank() == ROOT)
+        .map(|kind| {
+            let default = format!("run.{}", kind);
+            let path = MATCHES.as_ref().and_then(|m| m.value_of("export_path")).unwrap_or(&default);
+            let every = match MATCHES.as_ref().and_then(|m| m.value_of("export_every").and_then(|x|x.parse::<u64>().ok())) {
+                Some(w) if w > 0 => w,
+                _ => 10
+            };
+            let color = match MATCHES.as_ref().and_then(|m| m.value_of("export_color")) {
+                Some("speed") => ColorBy::Speed,
+                _ => ColorBy::Mass
+            };
+            match create_export(kind, path, every, color) {
+                Ok(x) => Mutex::new(x),
+                Err(e) => {
+                    eprintln!("unable to create {}: {}", path, e);
+                    std::process::exit(1);
+                }
+            }
+        });
+
+    /// The restart seed, then `--seed`, then a random one, always as decided by the root process.
+    pub static ref SEED : u64 = {
+        let mut seed = RESTART.as_ref().map(|x| x.seed)
+            .or(MATCHES.as_ref().and_then(|m| m.value_of("seed").and_then(|x|x.parse::<u64>().ok())))
+            .unwrap_or_else(rand::random);
+        WORLD.process_at_rank(ROOT).broadcast_into(&mut seed);
+        seed
     };
 
-    pub static ref BENCHMARK : bool = match MATCHES.as_ref().and_then(|m| m.value_of("mode")) {
-        Some("benchmark") => true,
+    pub static ref CHECKPOINTS : Option<Checkpoints> = MATCHES.as_ref().and_then(|m| m.value_of("checkpoint")).map(|path| {
+        Checkpoints {
+            path: path.into(),
+            every: match MATCHES.as_ref().and_then(|m| m.value_of("checkpoint_every").and_then(|x|x.parse::<u64>().ok())) {
+                Some(w) if w > 0 => w,
+                _ => 1000
+            },
+        }
+    });
+
+    /// Benchmark and record mode run without a window.
+    pub static ref HEADLESS : bool = match MATCHES.as_ref().and_then(|m| m.value_of("mode")) {
+        Some("benchmark") | Some("record") => true,
         _ => false
     };
 
+    pub static ref REPLAY : bool = MATCHES.as_ref().and_then(|m| m.value_of("mode")) == Some("replay");
+
+    pub static ref STEPS : u64 = match MATCHES.as_ref().and_then(|m| m.value_of("steps").and_then(|x|x.parse::<u64>().ok())) {
+        Some(w) if w > 0 => w,
+        _ => if MATCHES.as_ref().and_then(|m| m.value_of("mode")) == Some("record") { 1000 } else { 1 }
+    };
+
+    /// Only the root process records; in replay mode the file is read instead.
+    pub static ref TRAJECTORY : Option<Mutex<TrajectoryWriter>> = MATCHES.as_ref()
+        .and_then(|m| m.value_of("trajectory"))
+        .filter(|_| !*REPLAY && WORLD.rank() == ROOT)
+        .map(|path| {
+            let every = match MATCHES.as_ref().and_then(|m| m.value_of("trajectory_every").and_then(|x|x.parse::<u64>().ok())) {
+                Some(w) if w > 0 => w,
+                _ => 1
+            };
+            match TrajectoryWriter::create(path, every, *SIZE) {
+                Ok(x) => Mutex::new(x),
+                Err(e) => {
+                    eprintln!("unable to create trajectory {}: {}", path, e);
+                    std::process::exit(1);
+                }
+            }
+        });
+
+    pub static ref REPLAY_SPEED : f64 = match MATCHES.as_ref().and_then(|m| m.value_of("replay_speed").and_then(|x|x.parse::<f64>().ok())) {
+        Some(w) if w.is_finite() => w,
+        _ => 1.0
+    };
+
     pub static ref FPS_FLAG : bool = match MATCHES.as_ref().and_then(|m| m.value_of("fps")) {
         Some("yes") => true,
         _ => false
     };
 
+    pub static ref DISPLAY : Backend = match MATCHES.as_ref().and_then(|m| m.value_of("display")) {
+        Some("terminal") => Backend::Terminal,
+        _ => Backend::Sdl
+    };
+
+    pub static ref STEPPING : Stepping = match MATCHES.as_ref().and_then(|m| m.value_of("stepping")) {
+        Some("free") => Stepping::Free,
+        _ => Stepping::Frame
+    };
+
+    /// Shortest time between two frames of the window.
+    pub static ref FRAME_TIME : Option<Duration> = match MATCHES.as_ref().and_then(|m| m.value_of("max_fps").and_then(|x|x.parse::<f64>().ok())) {
+        Some(w) if w > 0.0 => Some(Duration::from_secs_f64(1.0 / w)),
+        _ => None
+    };
+
+    pub static ref HUD : bool = match MATCHES.as_ref().and_then(|m| m.value_of("hud")) {
+        Some("yes") => true,
+        _ => false
+    };
+
+    pub static ref TRACK : Tracking = MATCHES.as_ref()
+        .and_then(|m| m.value_of("track"))
+        .and_then(Tracking::parse)
+        .unwrap_or(Tracking::Off);
+
     pub static ref THREAD : usize = match MATCHES.as_ref().and_then(|m| m.value_of("thread").and_then(|x|x.parse::<usize>().ok())) {
         Some(w) if w > 0 => w,
         _ => 6
     };
 
+    pub static ref IC_RADIUS : f64 = match MATCHES.as_ref().and_then(|m| m.value_of("ic_radius").and_then(|x|x.parse::<f64>().ok())) {
+        Some(w) if w > 0.0 => w,
+        _ => WIDTH.min(*HEIGHT) / *SCALE_FACTOR / 10.0
+    };
+
+    pub static ref KING_W0 : f64 = match MATCHES.as_ref().and_then(|m| m.value_of("king_w0").and_then(|x|x.parse::<f64>().ok())) {
+        Some(w) if w > 0.0 => w,
+        _ => 6.0
+    };
+
+    pub static ref CENTRAL_MASS : Option<f64> = match MATCHES.as_ref().and_then(|m| m.value_of("central_mass").and_then(|x|x.parse::<f64>().ok())) {
+        Some(w) if w > 0.0 => Some(w),
+        _ => None
+    };
+
+    pub static ref IC_SPEC : Vec<Component> = {
+        let text = MATCHES.as_ref().and_then(|m| m.value_of("ic")).unwrap_or("uniform");
+        match parse_spec(text) {
+            Ok(x) => x,
+            Err(e) => {
+                if WORLD.rank() == ROOT {
+                    eprintln!("{}", e);
+                }
+                std::process::exit(1);
+            }
+        }
+    };
+
+    pub static ref FORCE : Box<dyn ForceLaw> = match MATCHES.as_ref().and_then(|m| m.value_of("force")) {
+        Some("coulomb") => Box::new(Coulomb { k: K_COULOMB }),
+        Some("lennard_jones") => Box::new(LennardJones { epsilon: LJ_EPSILON, sigma: LJ_SIGMA }),
+        Some("yukawa") => Box::new(Yukawa { g: G, lambda: YUKAWA_LAMBDA }),
+        _ => Box::new(Newtonian { g: G })
+    };
+
+    pub static ref FIELDS : Vec<ExternalField> = {
+        let mut fields = Vec::new();
+        let specs = MATCHES.as_ref().and_then(|m| m.values_of("field"));
+        let result = specs.into_iter().flatten()
+            .map(ExternalField::parse)
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .and_then(|mut x| {
+                fields.append(&mut x);
+                match MATCHES.as_ref().and_then(|m| m.value_of("field_file")) {
+                    Some(path) => load_fields(path).map(|mut x| fields.append(&mut x)),
+                    None => Ok(())
+                }
+            });
+        if let Err(e) = result {
+            if WORLD.rank() == ROOT {
+                eprintln!("{}", e);
+            }
+            std::process::exit(1);
+        }
+        fields
+    };
+
+    pub static ref DAMPING : Damping = {
+        let parse = |name| match MATCHES.as_ref().and_then(|m| m.value_of(name).and_then(|x|x.parse::<f64>().ok())) {
+            Some(w) if w > 0.0 => w,
+            _ => 0.0
+        };
+        Damping {
+            linear: parse("drag"),
+            quadratic: parse("quadratic_drag"),
+            langevin: parse("langevin"),
+            temperature: parse("temperature"),
+        }
+    };
+
     // pub static ref ROOT_PROC : Process<'static, SystemCommunicator> =  WORLD.process_at_rank(ROOT);
 }
 
@@ -101,9 +528,62 @@ pub const G: f64 = 5.0;
 pub const ALPHA: f64 = 0.001;
 pub const ROOT: i32 = 0;
 pub const MASS_RANGE: f64 = 50.0;
+pub const CHARGE_RANGE: f64 = 5.0;
+pub const K_COULOMB: f64 = 500.0;
+pub const LJ_EPSILON: f64 = 1.0;
+pub const LJ_SIGMA: f64 = 2.0 * RADIUS;
+pub const YUKAWA_LAMBDA: f64 = 50.0;
+
+/// Number of steps taken since the start of the run, including those before a restart.
+static STEP: AtomicU64 = AtomicU64::new(0);
+
+pub fn step() -> u64 {
+    STEP.load(Ordering::SeqCst)
+}
+
+/// Counts one more step and returns the new step number.
+pub fn advance() -> u64 {
+    STEP.fetch_add(1, Ordering::SeqCst) + 1
+}
+
+/// Settles the values the processes have to agree on. Every process calls this before anything
+/// else, so that the broadcasts behind them happen in the same order everywhere.
+pub fn synchronize() {
+    lazy_static::initialize(&SIZE);
+    lazy_static::initialize(&SEED);
+    if MATCHES.as_ref().and_then(|m| m.value_of("restart")).is_some() {
+        let mut step = RESTART.as_ref().map_or(0, |x| x.step);
+        WORLD.process_at_rank(ROOT).broadcast_into(&mut step);
+        STEP.store(step, Ordering::SeqCst);
+    }
+}
+
+/// Pinned bodies occupy the front of every body list and tracers the back, so that the
+/// structure-of-arrays engines can skip them by index range.
+pub fn kind_of(i: usize) -> Kind {
+    kind_at(i, *SIZE)
+}
+
+/// Kind of body `i` out of `count`, for engines that keep the kinds in place of storing them.
+pub fn kind_at(i: usize, count: usize) -> Kind {
+    if i < *PINNED {
+        Kind::Pinned
+    } else if i + *TRACERS >= count {
+        Kind::Tracer
+    } else {
+        Kind::Dynamic
+    }
+}
+
+/// Whether MPI may be called from the simulation thread of the window.
+pub fn mpi_threaded() -> bool {
+    UNIVERSE.1 >= Threading::Serialized
+}
 
+/// Prints the frame rate once a second with `--fps yes`, except on the terminal display,
+/// where it would land on the picture; its HUD shows the frame rate instead.
 pub fn show_fps(n: &mut usize, start: &mut SystemTime) {
-    if *FPS_FLAG {
+    if *FPS_FLAG && *DISPLAY != Backend::Terminal {
         let cur = std::time::SystemTime::now();
         let du = cur.duration_since(*start).unwrap().as_millis();
         if du >= 1000 {
@@ -0,0 +1,179 @@
+use std::f64::consts::PI;
+
+use rand::Rng;
+
+use crate::force::gaussian;
+use crate::global::*;
+use crate::initial::{BodyState, inside, virialise};
+
+/// Places `count` equal-mass bodies around the domain centre.
+///
+/// `radius` draws a radius in units of `--ic-radius` from the cumulative mass profile, `speed`
+/// draws a speed at that radius in arbitrary units; directions are isotropic in the plane and
+/// the speeds are then rescaled to virial equilibrium. Bodies falling outside the walls are
+/// redrawn.
+fn spherical<R, P, V>(count: usize, rng: &mut R, mut radius: P, mut speed: V) -> Vec<BodyState>
+    where R: Rng + ?Sized,
+          P: FnMut(&mut R) -> f64,
+          V: FnMut(f64, &mut R) -> f64 {
+    let centre_x = *WIDTH / *SCALE_FACTOR / 2.0;
+    let centre_y = *HEIGHT / *SCALE_FACTOR / 2.0;
+    let mut bodies = Vec::with_capacity(count);
+    while bodies.len() < count {
+        let r = radius(rng);
+        let theta = rng.gen_range(0.0, 2.0 * PI);
+        let x = centre_x + r * *IC_RADIUS * theta.cos();
+        let y = centre_y + r * *IC_RADIUS * theta.sin();
+        if !inside(x, y) {
+            continue;
+        }
+        let v = speed(r, rng);
+        let phi = rng.gen_range(0.0, 2.0 * PI);
+        bodies.push(BodyState {
+            x,
+            y,
+            vx: v * phi.cos(),
+            vy: v * phi.sin(),
+            m: MASS_RANGE / 2.0,
+            q: rng.gen_range(-CHARGE_RANGE, CHARGE_RANGE),
+        });
+    }
+    virialise(&mut bodies);
+    bodies
+}
+
+/// Plummer sphere, with speeds drawn from its distribution function (Aarseth, Henon & Wielen 1974).
+pub fn plummer<R: Rng + ?Sized>(count: usize, rng: &mut R) -> Vec<BodyState> {
+    spherical(count, rng, |rng| loop {
+        let u: f64 = rng.gen_range(EPSILON_U, 1.0);
+        let r = 1.0 / (u.powf(-2.0 / 3.0) - 1.0).sqrt();
+        if r < TRUNCATION {
+            return r;
+        }
+    }, |r, rng| {
+        let q = loop {
+            let q: f64 = rng.gen();
+            let g: f64 = rng.gen_range(0.0, 0.1);
+            if g < q * q * (1.0 - q * q).powf(3.5) {
+                break q;
+            }
+        };
+        q * 2.0_f64.sqrt() * (1.0 + r * r).powf(-0.25)
+    })
+}
+
+/// Hernquist profile. Speeds use the local isotropic dispersion of a Hernquist sphere
+/// approximated as `1 / (6 (1 + r))`.
+pub fn hernquist<R: Rng + ?Sized>(count: usize, rng: &mut R) -> Vec<BodyState> {
+    spherical(count, rng, |rng| loop {
+        let u: f64 = rng.gen_range(EPSILON_U, 1.0);
+        let r = u.sqrt() / (1.0 - u.sqrt());
+        if r < TRUNCATION {
+            return r;
+        }
+    }, |r, rng| {
+        let sigma = (1.0 / (6.0 * (1.0 + r))).sqrt();
+        let vx = sigma * gaussian(rng);
+        let vy = sigma * gaussian(rng);
+        (vx * vx + vy * vy).sqrt()
+    })
+}
+
+/// King model with central potential `w0`, in units of the core radius and velocity dispersion.
+pub fn king<R: Rng + ?Sized>(count: usize, w0: f64, rng: &mut R) -> Vec<BodyState> {
+    let profile = KingProfile::solve(w0);
+    spherical(count, rng, |rng| profile.sample_radius(rng.gen()), |r, rng| {
+        let w = profile.potential(r);
+        if w <= 0.0 {
+            return 0.0;
+        }
+        let v_max = (2.0 * w).sqrt();
+        let pdf = |v: f64| v * v * ((w - v * v / 2.0).exp() - 1.0);
+        let bound = (0..=64).map(|k| pdf(v_max * k as f64 / 64.0)).fold(0.0, f64::max) * 1.1;
+        loop {
+            let v = rng.gen_range(0.0, v_max);
+            if rng.gen_range(0.0, bound) < pdf(v) {
+                return v;
+            }
+        }
+    })
+}
+
+const EPSILON_U: f64 = 1e-9;
+const TRUNCATION: f64 = 20.0;
+
+/// Tabulated solution of the dimensionless King Poisson equation.
+struct KingProfile {
+    radius: Vec<f64>,
+    potential: Vec<f64>,
+    mass: Vec<f64>,
+}
+
+impl KingProfile {
+    fn density(w: f64) -> f64 {
+        if w <= 0.0 {
+            0.0
+        } else {
+            w.exp() * erf(w.sqrt()) - (4.0 * w / PI).sqrt() * (1.0 + 2.0 * w / 3.0)
+        }
+    }
+
+    /// Integrates `W'' + 2 W' / r = -9 rho(W) / rho(W0)` outwards until `W` reaches zero at the
+    /// tidal radius.
+    fn solve(w0: f64) -> Self {
+        const STEP: f64 = 1e-3;
+        let rho0 = Self::density(w0);
+        let derive = |r: f64, w: f64, dw: f64| {
+            (dw, -9.0 * Self::density(w) / rho0 - 2.0 * dw / r)
+        };
+        // series solution near the centre, where the density is still about rho0
+        let mut r = STEP;
+        let mut w = w0 - 1.5 * r * r;
+        let mut dw = -3.0 * r;
+        let mut mass = 4.0 / 3.0 * PI * r * r * r;
+        let mut profile = KingProfile {
+            radius: vec![0.0, r],
+            potential: vec![w0, w],
+            mass: vec![0.0, mass],
+        };
+        while w > 0.0 && r < 1000.0 {
+            let k1 = derive(r, w, dw);
+            let k2 = derive(r + STEP / 2.0, w + STEP / 2.0 * k1.0, dw + STEP / 2.0 * k1.1);
+            let k3 = derive(r + STEP / 2.0, w + STEP / 2.0 * k2.0, dw + STEP / 2.0 * k2.1);
+            let k4 = derive(r + STEP, w + STEP * k3.0, dw + STEP * k3.1);
+            w += STEP / 6.0 * (k1.0 + 2.0 * k2.0 + 2.0 * k3.0 + k4.0);
+            dw += STEP / 6.0 * (k1.1 + 2.0 * k2.1 + 2.0 * k3.1 + k4.1);
+            r += STEP;
+            mass += 4.0 * PI * r * r * Self::density(w) / rho0 * STEP;
+            profile.radius.push(r);
+            profile.potential.push(w.max(0.0));
+            profile.mass.push(mass);
+        }
+        profile
+    }
+
+    fn sample_radius(&self, u: f64) -> f64 {
+        let target = u * self.mass[self.mass.len() - 1];
+        let k = match self.mass.binary_search_by(|m| m.partial_cmp(&target).unwrap()) {
+            Ok(k) => k,
+            Err(k) => k.min(self.mass.len() - 1),
+        };
+        self.radius[k]
+    }
+
+    fn potential(&self, r: f64) -> f64 {
+        let k = match self.radius.binary_search_by(|x| x.partial_cmp(&r).unwrap()) {
+            Ok(k) => k,
+            Err(k) => k.min(self.radius.len() - 1),
+        };
+        self.potential[k]
+    }
+}
+
+/// Error function, Abramowitz & Stegun 7.1.26 (absolute error below 1.5e-7).
+fn erf(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.3275911 * x.abs());
+    let y = 1.0 - (((((1.061405429 * t - 1.453152027) * t) + 1.421413741) * t - 0.284496736) * t + 0.254829592)
+        * t * (-x * x).exp();
+    if x >= 0.0 { y } else { -y }
+}
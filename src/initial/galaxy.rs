@@ -0,0 +1,125 @@
+use std::f64::consts::PI;
+
+use rand::Rng;
+
+use crate::force::field_acceleration;
+use crate::global::*;
+use crate::initial::{BodyState, inside};
+
+/// An exponential disk of equal-mass bodies on circular orbits around a central body.
+#[derive(Copy, Clone, Debug)]
+pub struct Disk {
+    pub x: f64,
+    pub y: f64,
+    pub vx: f64,
+    pub vy: f64,
+    /// Exponential scale length; bodies are truncated at `TRUNCATION` scale lengths.
+    pub scale: f64,
+    pub central_mass: f64,
+    pub clockwise: bool,
+}
+
+const TRUNCATION: f64 = 5.0;
+
+impl Disk {
+    /// The central body comes first, so `--pinned 1` holds it in place.
+    ///
+    /// Circular speeds account for the central body, the disk mass enclosed by each radius
+    /// (treated as spherical) and the radial pull of any configured external fields.
+    pub fn generate<R: Rng + ?Sized>(&self, count: usize, rng: &mut R) -> Vec<BodyState> {
+        let mut bodies = Vec::with_capacity(count);
+        if count == 0 {
+            return bodies;
+        }
+        bodies.push(BodyState {
+            x: self.x,
+            y: self.y,
+            vx: self.vx,
+            vy: self.vy,
+            m: self.central_mass,
+            q: 0.0,
+        });
+        let m = MASS_RANGE / 2.0;
+        let disk_mass = m * (count - 1) as f64;
+        let sign = if self.clockwise { -1.0 } else { 1.0 };
+        while bodies.len() < count {
+            // the radial density r * exp(-r) of an exponential disk is a gamma(2) distribution
+            let u: f64 = 1.0 - rng.gen::<f64>();
+            let v: f64 = 1.0 - rng.gen::<f64>();
+            let r = -self.scale * (u.ln() + v.ln());
+            if r < 4.0 * RADIUS || r > TRUNCATION * self.scale {
+                continue;
+            }
+            let theta = rng.gen_range(0.0, 2.0 * PI);
+            let (x, y) = (self.x + r * theta.cos(), self.y + r * theta.sin());
+            if !inside(x, y) {
+                continue;
+            }
+            let enclosed = self.central_mass
+                + disk_mass * (1.0 - (1.0 + r / self.scale) * (-r / self.scale).exp());
+            let (fx, fy) = field_acceleration(&FIELDS, x, y, 0.0, 0.0);
+            let inward = -(fx * theta.cos() + fy * theta.sin());
+            let speed = (G * enclosed / r + r * inward.max(0.0)).sqrt();
+            bodies.push(BodyState {
+                x,
+                y,
+                vx: self.vx - sign * speed * theta.sin(),
+                vy: self.vy + sign * speed * theta.cos(),
+                m,
+                q: rng.gen_range(-CHARGE_RANGE, CHARGE_RANGE),
+            });
+        }
+        bodies
+    }
+}
+
+fn default_central_mass(count: usize) -> f64 {
+    match *CENTRAL_MASS {
+        Some(m) => m,
+        None => MASS_RANGE / 2.0 * count as f64
+    }
+}
+
+/// A single disk galaxy at the domain centre.
+pub fn disk<R: Rng + ?Sized>(count: usize, rng: &mut R) -> Vec<BodyState> {
+    Disk {
+        x: *WIDTH / *SCALE_FACTOR / 2.0,
+        y: *HEIGHT / *SCALE_FACTOR / 2.0,
+        vx: 0.0,
+        vy: 0.0,
+        scale: *IC_RADIUS,
+        central_mass: default_central_mass(count),
+        clockwise: false,
+    }.generate(count, rng)
+}
+
+/// Two equal disks on a parabolic encounter with pericentre distance `--ic-radius`, starting
+/// half a domain width apart and approaching each other.
+pub fn galaxies<R: Rng + ?Sized>(count: usize, rng: &mut R) -> Vec<BodyState> {
+    let first = count / 2;
+    let second = count - first;
+    let centre_x = *WIDTH / *SCALE_FACTOR / 2.0;
+    let centre_y = *HEIGHT / *SCALE_FACTOR / 2.0;
+    let separation = *WIDTH / *SCALE_FACTOR / 2.0;
+    let pericentre = IC_RADIUS.min(separation);
+
+    let mass = |n: usize| default_central_mass(n) + MASS_RANGE / 2.0 * n.saturating_sub(1) as f64;
+    let total = mass(first) + mass(second);
+    // zero orbital energy, with the angular momentum of a parabola through `pericentre`
+    let speed = (2.0 * G * total / separation).sqrt();
+    let tangential = (2.0 * G * total * pericentre).sqrt() / separation;
+    let radial = (speed * speed - tangential * tangential).max(0.0).sqrt();
+
+    let galaxy = |n: usize, side: f64, share: f64| Disk {
+        x: centre_x + side * separation / 2.0,
+        y: centre_y,
+        vx: -side * radial * share,
+        vy: -side * tangential * share,
+        scale: *IC_RADIUS / 2.0,
+        central_mass: default_central_mass(n),
+        clockwise: false,
+    };
+    let mut bodies = galaxy(first, -1.0, mass(second) / total).generate(first, rng);
+    bodies.extend(galaxy(second, 1.0, mass(first) / total).generate(second, rng));
+    bodies
+}
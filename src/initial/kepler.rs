@@ -0,0 +1,153 @@
+use std::f64::consts::PI;
+
+use rand::Rng;
+
+use crate::global::*;
+use crate::initial::BodyState;
+
+/// Planar orbital elements of one body around the central star. Angles are in radians.
+#[derive(Copy, Clone, Debug)]
+pub struct Orbit {
+    pub mass: f64,
+    pub semi_major_axis: f64,
+    pub eccentricity: f64,
+    /// Argument of periapsis, measured from the x axis.
+    pub periapsis: f64,
+    pub mean_anomaly: f64,
+}
+
+impl Orbit {
+    /// Solves Kepler's equation `E - e sin E = M` by Newton iteration.
+    pub fn eccentric_anomaly(&self) -> f64 {
+        let e = self.eccentricity;
+        let m = self.mean_anomaly.rem_euclid(2.0 * PI);
+        let mut anomaly = if e > 0.8 { PI } else { m };
+        for _ in 0..50 {
+            let delta = (anomaly - e * anomaly.sin() - m) / (1.0 - e * anomaly.cos());
+            anomaly -= delta;
+            if delta.abs() < 1e-14 {
+                break;
+            }
+        }
+        anomaly
+    }
+
+    /// Position and velocity relative to a star of mass `star_mass`.
+    pub fn state(&self, star_mass: f64) -> (f64, f64, f64, f64) {
+        let a = self.semi_major_axis;
+        let e = self.eccentricity;
+        let anomaly = self.eccentric_anomaly();
+        let (sin_e, cos_e) = anomaly.sin_cos();
+        let root = (1.0 - e * e).sqrt();
+        let motion = (G * (star_mass + self.mass) / (a * a * a)).sqrt();
+        let x = a * (cos_e - e);
+        let y = a * root * sin_e;
+        let vx = -a * motion * sin_e / (1.0 - e * cos_e);
+        let vy = a * motion * root * cos_e / (1.0 - e * cos_e);
+        let (sin_w, cos_w) = self.periapsis.sin_cos();
+        (x * cos_w - y * sin_w, x * sin_w + y * cos_w, vx * cos_w - vy * sin_w, vx * sin_w + vy * cos_w)
+    }
+}
+
+/// Builds a star with planets from orbital elements.
+pub struct KeplerSystem {
+    pub x: f64,
+    pub y: f64,
+    pub star_mass: f64,
+    pub orbits: Vec<Orbit>,
+}
+
+impl KeplerSystem {
+    pub fn new(x: f64, y: f64, star_mass: f64) -> Self {
+        KeplerSystem { x, y, star_mass, orbits: Vec::new() }
+    }
+
+    pub fn planet(mut self, orbit: Orbit) -> Self {
+        self.orbits.push(orbit);
+        self
+    }
+
+    /// The star comes first. Velocities are shifted so that the total momentum is zero and the
+    /// system does not drift.
+    pub fn build(&self) -> Vec<BodyState> {
+        let mut bodies = vec![BodyState { x: self.x, y: self.y, vx: 0.0, vy: 0.0, m: self.star_mass, q: 0.0 }];
+        for orbit in &self.orbits {
+            let (x, y, vx, vy) = orbit.state(self.star_mass);
+            bodies.push(BodyState { x: self.x + x, y: self.y + y, vx, vy, m: orbit.mass, q: 0.0 });
+        }
+        let total: f64 = bodies.iter().map(|b| b.m).sum();
+        let drift_x = bodies.iter().map(|b| b.m * b.vx).sum::<f64>() / total;
+        let drift_y = bodies.iter().map(|b| b.m * b.vy).sum::<f64>() / total;
+        for b in &mut bodies {
+            b.vx -= drift_x;
+            b.vy -= drift_y;
+        }
+        bodies
+    }
+}
+
+/// Reads `mass semi_major_axis eccentricity periapsis mean_anomaly` per line, angles in degrees.
+/// Blank lines and lines starting with `#` are skipped.
+pub fn load_orbits(path: &str) -> Result<Vec<Orbit>, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    let mut orbits = Vec::new();
+    for (n, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let values = line.split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|x| !x.is_empty())
+            .map(|x| x.parse::<f64>().map_err(|_| format!("{}:{}: invalid number `{}`", path, n + 1, x)))
+            .collect::<Result<Vec<_>, _>>()?;
+        if values.len() != 5 {
+            return Err(format!("{}:{}: expected 5 orbital elements, got {}", path, n + 1, values.len()));
+        }
+        if values[1] <= 0.0 || values[2] < 0.0 || values[2] >= 1.0 {
+            return Err(format!("{}:{}: need a positive semi-major axis and 0 <= e < 1", path, n + 1));
+        }
+        orbits.push(Orbit {
+            mass: values[0],
+            semi_major_axis: values[1],
+            eccentricity: values[2],
+            periapsis: values[3].to_radians(),
+            mean_anomaly: values[4].to_radians(),
+        });
+    }
+    Ok(orbits)
+}
+
+/// A star at the domain centre with the planets from `--planets`, topped up to `count` bodies
+/// with low-mass, nearly circular orbits between a half and twice `--ic-radius`.
+pub fn kepler<R: Rng + ?Sized>(count: usize, rng: &mut R) -> Vec<BodyState> {
+    let orbits = match MATCHES.as_ref().and_then(|m| m.value_of("planets")) {
+        Some(path) => match load_orbits(path) {
+            Ok(x) => x,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        },
+        None => Vec::new()
+    };
+    let mut system = KeplerSystem::new(
+        *WIDTH / *SCALE_FACTOR / 2.0,
+        *HEIGHT / *SCALE_FACTOR / 2.0,
+        CENTRAL_MASS.unwrap_or(MASS_RANGE * 1000.0),
+    );
+    for orbit in orbits.into_iter().take(count.saturating_sub(1)) {
+        system = system.planet(orbit);
+    }
+    while system.orbits.len() + 1 < count {
+        system = system.planet(Orbit {
+            mass: rng.gen_range(0.0, MASS_RANGE / 100.0),
+            semi_major_axis: rng.gen_range(*IC_RADIUS / 2.0, *IC_RADIUS * 2.0),
+            eccentricity: rng.gen_range(0.0, 0.1),
+            periapsis: rng.gen_range(0.0, 2.0 * PI),
+            mean_anomaly: rng.gen_range(0.0, 2.0 * PI),
+        });
+    }
+    let mut bodies = system.build();
+    bodies.truncate(count);
+    bodies
+}
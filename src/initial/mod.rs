@@ -0,0 +1,118 @@
+use std::f64::EPSILON;
+
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+use crate::geometry::{Kind, SimpleBody};
+use crate::global::*;
+
+pub use cluster::*;
+pub use galaxy::*;
+pub use kepler::*;
+pub use spec::*;
+
+mod cluster;
+mod galaxy;
+mod kepler;
+mod spec;
+
+/// Initial state of one body, independent of the layout each engine keeps it in.
+#[derive(Copy, Clone, Debug)]
+pub struct BodyState {
+    pub x: f64,
+    pub y: f64,
+    pub vx: f64,
+    pub vy: f64,
+    pub m: f64,
+    pub q: f64,
+}
+
+impl BodyState {
+    pub fn to_simple(&self, kind: Kind) -> SimpleBody {
+        SimpleBody {
+            x: self.x,
+            y: self.y,
+            m: self.m,
+            q: self.q,
+            kind,
+            vx: self.vx,
+            vy: self.vy,
+            ax: 0.0,
+            ay: 0.0,
+        }
+    }
+}
+
+/// Generates `count` bodies according to the `--ic` description, or takes them from `--input`
+/// or `--restart`.
+pub fn generate(count: usize) -> Vec<BodyState> {
+    if let Some(checkpoint) = RESTART.as_ref() {
+        return checkpoint.bodies.clone();
+    }
+    if let Some(bodies) = INPUT.as_ref() {
+        let mut bodies = bodies.clone();
+        bodies.truncate(count);
+        return bodies;
+    }
+    match build(&IC_SPEC, count, &mut StdRng::seed_from_u64(*SEED)) {
+        Ok(bodies) => bodies,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Bodies at rest, spread uniformly over the domain with random mass.
+pub fn uniform<R: Rng + ?Sized>(count: usize, rng: &mut R) -> Vec<BodyState> {
+    let real_width = *WIDTH / *SCALE_FACTOR;
+    let real_height = *HEIGHT / *SCALE_FACTOR;
+    (0..count).map(|_| BodyState {
+        x: rng.gen_range(RADIUS + EPSILON, real_width - RADIUS),
+        y: rng.gen_range(RADIUS + EPSILON, real_height - RADIUS),
+        vx: 0.0,
+        vy: 0.0,
+        m: rng.gen_range(0.0, MASS_RANGE),
+        q: rng.gen_range(-CHARGE_RANGE, CHARGE_RANGE),
+    }).collect()
+}
+
+/// Whether a body at `(x, y)` lies strictly inside the walls.
+pub fn inside(x: f64, y: f64) -> bool {
+    let real_width = *WIDTH / *SCALE_FACTOR;
+    let real_height = *HEIGHT / *SCALE_FACTOR;
+    x - RADIUS > 0.0 && x + RADIUS < real_width && y - RADIUS > 0.0 && y + RADIUS < real_height
+}
+
+/// Removes the centre of mass drift and rescales all velocities so that `2T + W = 0`,
+/// with `W` the Newtonian potential energy of the bodies themselves.
+pub fn virialise(bodies: &mut [BodyState]) {
+    let total: f64 = bodies.iter().map(|b| b.m).sum();
+    if total <= 0.0 {
+        return;
+    }
+    let drift_x = bodies.iter().map(|b| b.m * b.vx).sum::<f64>() / total;
+    let drift_y = bodies.iter().map(|b| b.m * b.vy).sum::<f64>() / total;
+    for b in bodies.iter_mut() {
+        b.vx -= drift_x;
+        b.vy -= drift_y;
+    }
+    let mut potential = 0.0;
+    for i in 0..bodies.len() {
+        for j in i + 1..bodies.len() {
+            let delta_x = bodies[i].x - bodies[j].x;
+            let delta_y = bodies[i].y - bodies[j].y;
+            let dist = (delta_x * delta_x + delta_y * delta_y).sqrt().max(2.0 * RADIUS);
+            potential -= G * bodies[i].m * bodies[j].m / dist;
+        }
+    }
+    let kinetic: f64 = bodies.iter().map(|b| 0.5 * b.m * (b.vx * b.vx + b.vy * b.vy)).sum();
+    if kinetic <= 0.0 {
+        return;
+    }
+    let scale = (-potential / (2.0 * kinetic)).sqrt();
+    for b in bodies.iter_mut() {
+        b.vx *= scale;
+        b.vy *= scale;
+    }
+}
@@ -0,0 +1,316 @@
+use std::f64::consts::PI;
+use std::f64::EPSILON;
+
+use rand::Rng;
+
+use crate::force::gaussian;
+use crate::global::*;
+use crate::initial::*;
+
+/// What one component of an initial condition description places.
+#[derive(Clone, Debug)]
+pub enum Shape {
+    Uniform,
+    Square { spacing: f64 },
+    Hex { spacing: f64 },
+    Ring { radius: f64 },
+    Shell { inner: f64, outer: f64 },
+    Blob { sigma: f64 },
+    Image { path: String, size: Option<f64> },
+    /// One of the physical models, which sit at the domain centre unless `x` and `y` move them.
+    Model(String),
+}
+
+/// One `;`-separated part of an initial condition description such as
+/// `ring n=200 r=40; blob sigma=10 dispersion=0.5`.
+#[derive(Clone, Debug)]
+pub struct Component {
+    pub shape: Shape,
+    pub count: Option<usize>,
+    pub x: f64,
+    pub y: f64,
+    pub mass: Option<f64>,
+    pub dispersion: f64,
+}
+
+const MODELS: [&str; 7] = ["plummer", "king", "hernquist", "disk", "galaxies", "kepler", "uniform"];
+
+pub fn parse_spec(text: &str) -> Result<Vec<Component>, String> {
+    let components = text.split(';')
+        .map(|x| x.trim())
+        .filter(|x| !x.is_empty())
+        .map(parse_component)
+        .collect::<Result<Vec<_>, _>>()?;
+    if components.is_empty() {
+        return Err("empty initial condition description".to_string());
+    }
+    Ok(components)
+}
+
+fn parse_component(text: &str) -> Result<Component, String> {
+    let mut tokens = text.split_whitespace();
+    let kind = tokens.next().unwrap_or("");
+    let mut params = Vec::new();
+    for token in tokens {
+        let mut pair = token.splitn(2, '=');
+        match (pair.next(), pair.next()) {
+            (Some(key), Some(value)) if !value.is_empty() => params.push((key, value)),
+            _ => return Err(format!("in `{}`: expected key=value, got `{}`", text, token)),
+        }
+    }
+    let number = |key: &str, default: f64| -> Result<f64, String> {
+        match params.iter().rev().find(|p| p.0 == key) {
+            Some((_, value)) => value.parse::<f64>()
+                .map_err(|_| format!("in `{}`: `{}` is not a number", text, value)),
+            None => Ok(default)
+        }
+    };
+    let allowed: &[&str] = match kind {
+        "square" | "hex" => &["spacing"],
+        "ring" => &["r"],
+        "shell" => &["inner", "outer"],
+        "blob" => &["sigma"],
+        "image" => &["file", "size"],
+        _ => &[],
+    };
+    for (key, _) in &params {
+        if !["n", "x", "y", "mass", "dispersion"].contains(key) && !allowed.contains(key) {
+            return Err(format!("in `{}`: unknown parameter `{}` for `{}`", text, key, kind));
+        }
+        // uniform bodies fill the whole domain, so there is nothing to move
+        if kind == "uniform" && (*key == "x" || *key == "y") {
+            return Err(format!("in `{}`: `uniform` fills the domain and takes no `{}`", text, key));
+        }
+    }
+    let shape = match kind {
+        "uniform" => Shape::Uniform,
+        "square" => Shape::Square { spacing: number("spacing", 4.0 * RADIUS)? },
+        "hex" => Shape::Hex { spacing: number("spacing", 4.0 * RADIUS)? },
+        "ring" => Shape::Ring { radius: number("r", *IC_RADIUS)? },
+        "shell" => {
+            let (inner, outer) = (number("inner", *IC_RADIUS / 2.0)?, number("outer", *IC_RADIUS)?);
+            if !(inner >= 0.0 && inner <= outer) {
+                return Err(format!("in `{}`: shell needs 0 <= inner <= outer, got {} and {}", text, inner, outer));
+            }
+            Shape::Shell { inner, outer }
+        }
+        "blob" => Shape::Blob { sigma: number("sigma", *IC_RADIUS / 2.0)? },
+        "image" => Shape::Image {
+            path: match params.iter().find(|p| p.0 == "file") {
+                Some((_, path)) => path.to_string(),
+                None => return Err(format!("in `{}`: image needs file=PATH", text))
+            },
+            size: match params.iter().any(|p| p.0 == "size") {
+                true => Some(number("size", 0.0)?),
+                false => None
+            },
+        },
+        x if MODELS.contains(&x) => Shape::Model(x.to_string()),
+        _ => return Err(format!("unknown initial condition `{}`", kind)),
+    };
+    let count = match params.iter().rev().find(|p| p.0 == "n") {
+        Some((_, value)) => Some(value.parse::<usize>()
+            .map_err(|_| format!("in `{}`: `{}` is not a body count", text, value))?),
+        None => None
+    };
+    Ok(Component {
+        shape,
+        count,
+        x: number("x", *WIDTH / *SCALE_FACTOR / 2.0)?,
+        y: number("y", *HEIGHT / *SCALE_FACTOR / 2.0)?,
+        mass: match params.iter().any(|p| p.0 == "mass") {
+            true => Some(number("mass", 0.0)?),
+            false => None
+        },
+        dispersion: number("dispersion", 0.0)?,
+    })
+}
+
+/// Builds exactly `count` bodies from `components`.
+///
+/// Components with `n=` get that many bodies, the others share what is left evenly. Bodies
+/// beyond `count` are dropped and a shortfall is topped up with uniformly placed bodies.
+pub fn build<R: Rng + ?Sized>(components: &[Component], count: usize, rng: &mut R) -> Result<Vec<BodyState>, String> {
+    let fixed: usize = components.iter().filter_map(|c| c.count).sum();
+    let shared = components.iter().filter(|c| c.count.is_none()).count();
+    let left = count.saturating_sub(fixed);
+    let mut bodies = Vec::with_capacity(count);
+    let mut k = 0;
+    for c in components {
+        let n = match c.count {
+            Some(n) => n,
+            None => {
+                k += 1;
+                left / shared + if k <= left % shared { 1 } else { 0 }
+            }
+        };
+        let mut part = c.generate(n, rng)?;
+        for b in &mut part {
+            if let Some(m) = c.mass {
+                b.m = m;
+            }
+            if c.dispersion > 0.0 {
+                b.vx += c.dispersion * gaussian(rng);
+                b.vy += c.dispersion * gaussian(rng);
+            }
+        }
+        bodies.append(&mut part);
+    }
+    bodies.truncate(count);
+    let missing = count - bodies.len();
+    bodies.append(&mut uniform(missing, rng));
+    Ok(bodies)
+}
+
+impl Component {
+    fn generate<R: Rng + ?Sized>(&self, n: usize, rng: &mut R) -> Result<Vec<BodyState>, String> {
+        let real_width = *WIDTH / *SCALE_FACTOR;
+        let real_height = *HEIGHT / *SCALE_FACTOR;
+        let positions = match &self.shape {
+            Shape::Uniform => return Ok(uniform(n, rng)),
+            Shape::Model(name) => {
+                let mut bodies = match name.as_str() {
+                    "plummer" => plummer(n, rng),
+                    "king" => king(n, *KING_W0, rng),
+                    "hernquist" => hernquist(n, rng),
+                    "disk" => disk(n, rng),
+                    "galaxies" => galaxies(n, rng),
+                    "kepler" => kepler(n, rng),
+                    _ => uniform(n, rng),
+                };
+                let (dx, dy) = (self.x - real_width / 2.0, self.y - real_height / 2.0);
+                if dx != 0.0 || dy != 0.0 {
+                    for b in &mut bodies {
+                        b.x = (b.x + dx).max(RADIUS + EPSILON).min(real_width - RADIUS - EPSILON);
+                        b.y = (b.y + dy).max(RADIUS + EPSILON).min(real_height - RADIUS - EPSILON);
+                    }
+                }
+                return Ok(bodies);
+            }
+            Shape::Square { spacing } => lattice(n, *spacing, false),
+            Shape::Hex { spacing } => lattice(n, *spacing, true),
+            Shape::Ring { radius } => (0..n).map(|i| {
+                let theta = 2.0 * PI * i as f64 / n as f64;
+                (radius * theta.cos(), radius * theta.sin())
+            }).collect(),
+            Shape::Shell { inner, outer } => (0..n).map(|_| {
+                let r = rng.gen_range(inner * inner, outer * outer + EPSILON).sqrt();
+                let theta = rng.gen_range(0.0, 2.0 * PI);
+                (r * theta.cos(), r * theta.sin())
+            }).collect(),
+            Shape::Blob { sigma } => (0..n).map(|_| (sigma * gaussian(rng), sigma * gaussian(rng))).collect(),
+            Shape::Image { path, size } => image(path, *size, n, rng)?,
+        };
+        Ok(positions.into_iter().map(|(x, y)| BodyState {
+            x: (self.x + x).max(RADIUS + EPSILON).min(real_width - RADIUS - EPSILON),
+            y: (self.y + y).max(RADIUS + EPSILON).min(real_height - RADIUS - EPSILON),
+            vx: 0.0,
+            vy: 0.0,
+            m: MASS_RANGE / 2.0,
+            q: rng.gen_range(-CHARGE_RANGE, CHARGE_RANGE),
+        }).collect())
+    }
+}
+
+/// Offsets of `n` lattice sites around the origin; a hex lattice shifts every other row by
+/// half a spacing and packs the rows closer.
+fn lattice(n: usize, spacing: f64, hex: bool) -> Vec<(f64, f64)> {
+    let cols = (n as f64).sqrt().ceil().max(1.0) as usize;
+    let rows = (n + cols - 1) / cols;
+    let row_height = if hex { spacing * 3.0_f64.sqrt() / 2.0 } else { spacing };
+    (0..n).map(|i| {
+        let (row, col) = (i / cols, i % cols);
+        let shift = if hex && row % 2 == 1 { spacing / 2.0 } else { 0.0 };
+        ((col as f64 - (cols - 1) as f64 / 2.0) * spacing + shift,
+         (row as f64 - (rows - 1) as f64 / 2.0) * row_height)
+    }).collect()
+}
+
+/// Samples offsets with probability proportional to pixel brightness, with the image scaled to
+/// `size` simulation units wide (or to fit the domain) and centred on the origin.
+fn image<R: Rng + ?Sized>(path: &str, size: Option<f64>, n: usize, rng: &mut R) -> Result<Vec<(f64, f64)>, String> {
+    let (width, height, brightness) = read_ppm(path)?;
+    let mut cumulative = Vec::with_capacity(brightness.len());
+    let mut total = 0.0;
+    for b in &brightness {
+        total += b;
+        cumulative.push(total);
+    }
+    if total <= 0.0 {
+        return Err(format!("{}: image is completely dark", path));
+    }
+    let scale = match size {
+        Some(s) => s / width as f64,
+        None => (*WIDTH / width as f64).min(*HEIGHT / height as f64) / *SCALE_FACTOR
+    };
+    Ok((0..n).map(|_| {
+        let target = rng.gen_range(0.0, total);
+        let k = match cumulative.binary_search_by(|c| c.partial_cmp(&target).unwrap()) {
+            Ok(k) => k,
+            Err(k) => k.min(cumulative.len() - 1),
+        };
+        let px = (k % width) as f64 + rng.gen::<f64>();
+        let py = (k / width) as f64 + rng.gen::<f64>();
+        ((px - width as f64 / 2.0) * scale, (py - height as f64 / 2.0) * scale)
+    }).collect())
+}
+
+/// Reads a binary (`P6`) or plain (`P3`) PPM file into per-pixel brightness in `[0, 1]`.
+pub fn read_ppm(path: &str) -> Result<(usize, usize, Vec<f64>), String> {
+    let data = std::fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+    let mut pos = 0;
+    let mut header = Vec::new();
+    while header.len() < 4 {
+        while pos < data.len() && (data[pos] as char).is_whitespace() {
+            pos += 1;
+        }
+        if pos < data.len() && data[pos] == b'#' {
+            while pos < data.len() && data[pos] != b'\n' {
+                pos += 1;
+            }
+            continue;
+        }
+        let start = pos;
+        while pos < data.len() && !(data[pos] as char).is_whitespace() {
+            pos += 1;
+        }
+        if start == pos {
+            return Err(format!("{}: truncated ppm header", path));
+        }
+        header.push(String::from_utf8_lossy(&data[start..pos]).to_string());
+    }
+    let field = |k: usize, name: &str| header[k].parse::<usize>()
+        .map_err(|_| format!("{}: invalid ppm {} `{}`", path, name, header[k]));
+    let (width, height, max) = (field(1, "width")?, field(2, "height")?, field(3, "maximum")?);
+    if width == 0 || height == 0 || max == 0 || max > 65535 {
+        return Err(format!("{}: unsupported ppm dimensions {}x{} with maximum {}", path, width, height, max));
+    }
+    let samples: Vec<usize> = match header[0].as_str() {
+        "P6" => {
+            let body = &data[(pos + 1).min(data.len())..];
+            let depth = if max < 256 { 1 } else { 2 };
+            if body.len() < width * height * 3 * depth {
+                return Err(format!("{}: expected {} bytes of pixel data, found {}", path, width * height * 3 * depth, body.len()));
+            }
+            body.chunks(depth).take(width * height * 3)
+                .map(|c| if depth == 1 { c[0] as usize } else { (c[0] as usize) << 8 | c[1] as usize })
+                .collect()
+        }
+        "P3" => {
+            let text = String::from_utf8_lossy(&data[pos..]);
+            let samples = text.split_whitespace()
+                .take(width * height * 3)
+                .map(|x| x.parse::<usize>().map_err(|_| format!("{}: invalid sample `{}`", path, x)))
+                .collect::<Result<Vec<_>, _>>()?;
+            if samples.len() < width * height * 3 {
+                return Err(format!("{}: expected {} samples, found {}", path, width * height * 3, samples.len()));
+            }
+            samples
+        }
+        magic => return Err(format!("{}: unsupported ppm format `{}`, expected P3 or P6", path, magic)),
+    };
+    let brightness = samples.chunks(3)
+        .map(|c| (c[0] + c[1] + c[2]) as f64 / (3 * max) as f64)
+        .collect();
+    Ok((width, height, brightness))
+}
@@ -0,0 +1,197 @@
+use std::convert::TryInto;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use crate::global::*;
+use crate::initial::BodyState;
+
+/// Checkpoint files start with this magic, followed by a `u32` version, all little-endian.
+pub const CHECKPOINT_MAGIC: &[u8; 4] = b"NBCK";
+pub const CHECKPOINT_VERSION: u32 = 2;
+
+/// Options that change the trajectory; a restart refuses to continue under different values.
+pub const CONFIG_KEYS: &[&str] = &[
+    "engine", "width", "height", "scale", "force", "field", "field_file", "light_speed",
+    "pinned", "tracers", "drag", "quadratic_drag", "langevin", "temperature",
+];
+
+/// Everything needed to continue a run: the step counter, the seed the Langevin kicks are
+/// derived from, the configuration, the body state and, for the engines that kick with the
+/// acceleration of the last step, that acceleration.
+///
+/// Layout: magic, `u32` version, `u64` step, `f64` time, `u64` seed, `u32` number of config
+/// entries as pairs of `u32`-length-prefixed strings, `u64` body count and records of
+/// `x y vx vy m q` as `f64`, then a `u64` count that is either zero or the body count and
+/// that many `ax ay` pairs as `f64`.
+#[derive(Clone, Debug)]
+pub struct Checkpoint {
+    pub step: u64,
+    pub seed: u64,
+    pub config: Vec<(String, String)>,
+    pub bodies: Vec<BodyState>,
+    pub accelerations: Vec<(f64, f64)>,
+}
+
+impl Checkpoint {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(64 + self.bodies.len() * 6 * 8 + self.accelerations.len() * 2 * 8);
+        bytes.extend_from_slice(CHECKPOINT_MAGIC);
+        bytes.extend_from_slice(&CHECKPOINT_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&self.step.to_le_bytes());
+        bytes.extend_from_slice(&(self.step as f64 * ALPHA).to_le_bytes());
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        bytes.extend_from_slice(&(self.config.len() as u32).to_le_bytes());
+        for (key, value) in &self.config {
+            for text in &[key, value] {
+                bytes.extend_from_slice(&(text.len() as u32).to_le_bytes());
+                bytes.extend_from_slice(text.as_bytes());
+            }
+        }
+        bytes.extend_from_slice(&(self.bodies.len() as u64).to_le_bytes());
+        for b in &self.bodies {
+            for v in &[b.x, b.y, b.vx, b.vy, b.m, b.q] {
+                bytes.extend_from_slice(&v.to_le_bytes());
+            }
+        }
+        bytes.extend_from_slice(&(self.accelerations.len() as u64).to_le_bytes());
+        for &(ax, ay) in &self.accelerations {
+            bytes.extend_from_slice(&ax.to_le_bytes());
+            bytes.extend_from_slice(&ay.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Checkpoint, String> {
+        let mut offset = 0;
+        let mut take = |n: usize| -> Result<&[u8], String> {
+            if data.len() < offset + n {
+                return Err(format!("truncated at byte {}", data.len()));
+            }
+            offset += n;
+            Ok(&data[offset - n..offset])
+        };
+        if take(4)? != CHECKPOINT_MAGIC {
+            return Err("not a checkpoint file (bad magic)".to_string());
+        }
+        let version = u32::from_le_bytes(take(4)?.try_into().unwrap());
+        if version != CHECKPOINT_VERSION {
+            return Err(format!("unsupported version {}, expected {}", version, CHECKPOINT_VERSION));
+        }
+        let step = u64::from_le_bytes(take(8)?.try_into().unwrap());
+        take(8)?;
+        let seed = u64::from_le_bytes(take(8)?.try_into().unwrap());
+        let entries = u32::from_le_bytes(take(4)?.try_into().unwrap());
+        let mut config = Vec::new();
+        for _ in 0..entries {
+            let mut pair = Vec::with_capacity(2);
+            for _ in 0..2 {
+                let len = u32::from_le_bytes(take(4)?.try_into().unwrap()) as usize;
+                let text = String::from_utf8(take(len)?.to_vec()).map_err(|_| "config entry is not utf-8".to_string())?;
+                pair.push(text);
+            }
+            let value = pair.pop().unwrap();
+            config.push((pair.pop().unwrap(), value));
+        }
+        let count = u64::from_le_bytes(take(8)?.try_into().unwrap());
+        // the count is only trusted as far as the file can hold that many 48 byte records
+        let mut bodies = Vec::with_capacity((count as usize).min(data.len() / 48));
+        for _ in 0..count {
+            let mut values = [0.0; 6];
+            for v in values.iter_mut() {
+                *v = f64::from_le_bytes(take(8)?.try_into().unwrap());
+            }
+            bodies.push(BodyState { x: values[0], y: values[1], vx: values[2], vy: values[3], m: values[4], q: values[5] });
+        }
+        let saved = u64::from_le_bytes(take(8)?.try_into().unwrap());
+        if saved != 0 && saved != count {
+            return Err(format!("{} accelerations for {} bodies", saved, count));
+        }
+        let mut accelerations = Vec::with_capacity(bodies.len().min(saved as usize));
+        for _ in 0..saved {
+            let ax = f64::from_le_bytes(take(8)?.try_into().unwrap());
+            let ay = f64::from_le_bytes(take(8)?.try_into().unwrap());
+            accelerations.push((ax, ay));
+        }
+        if offset != data.len() {
+            return Err(format!("{} trailing bytes", data.len() - offset));
+        }
+        Ok(Checkpoint { step, seed, config, bodies, accelerations })
+    }
+
+    /// Names the options whose values differ from those of the current run.
+    pub fn mismatches(&self) -> Vec<String> {
+        let current = current_config();
+        CONFIG_KEYS.iter()
+            .filter_map(|key| {
+                let find = |config: &[(String, String)]| config.iter()
+                    .find(|(k, _)| k == key).map(|(_, v)| v.clone()).unwrap_or_default();
+                let (old, new) = (find(&self.config), find(&current));
+                if old == new { None } else { Some(format!("--{}: checkpoint has `{}`, run has `{}`", key, old, new)) }
+            })
+            .collect()
+    }
+}
+
+/// The values of [`CONFIG_KEYS`] given on the command line, with multiple values joined by `;`.
+pub fn current_config() -> Vec<(String, String)> {
+    CONFIG_KEYS.iter().map(|key| {
+        let value = MATCHES.as_ref()
+            .and_then(|m| m.values_of(key))
+            .map(|x| x.collect::<Vec<_>>().join(";"))
+            .unwrap_or_default();
+        (key.to_string(), value)
+    }).collect()
+}
+
+pub fn load_checkpoint(path: &str) -> Result<Checkpoint, String> {
+    let data = std::fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+    let checkpoint = Checkpoint::from_bytes(&data).map_err(|e| format!("{}: {}", path, e))?;
+    let mismatches = checkpoint.mismatches();
+    if !mismatches.is_empty() {
+        return Err(format!("{}: cannot restart with a different configuration\n  {}", path, mismatches.join("\n  ")));
+    }
+    Ok(checkpoint)
+}
+
+/// Overwrites `path` with a checkpoint every `every` steps.
+pub struct Checkpoints {
+    pub path: PathBuf,
+    pub every: u64,
+}
+
+impl Checkpoints {
+    pub fn due(&self, step: u64) -> bool {
+        step % self.every == 0
+    }
+
+    /// Writes to a temporary file first, so a crash while writing keeps the previous checkpoint.
+    pub fn write(&self, checkpoint: &Checkpoint) -> std::io::Result<()> {
+        let tmp = self.path.with_extension("tmp");
+        {
+            let mut out = BufWriter::new(File::create(&tmp)?);
+            out.write_all(&checkpoint.to_bytes())?;
+            out.flush()?;
+            out.get_ref().sync_all()?;
+        }
+        std::fs::rename(&tmp, &self.path)
+    }
+}
+
+/// Writes a checkpoint at `step` if `--checkpoint` is set and the step is due. `state` gives
+/// the bodies and their carried accelerations, if any, and is only called when a checkpoint
+/// is actually taken.
+pub fn checkpoint<F>(step: u64, state: F) -> Result<(), String>
+    where F: FnOnce() -> (Vec<BodyState>, Option<Vec<(f64, f64)>>) {
+    if let Some(checkpoints) = CHECKPOINTS.as_ref() {
+        if step > 0 && checkpoints.due(step) {
+            let (bodies, accelerations) = state();
+            let accelerations = accelerations.unwrap_or_default();
+            let checkpoint = Checkpoint { step, seed: *SEED, config: current_config(), bodies, accelerations };
+            if let Err(e) = checkpoints.write(&checkpoint) {
+                return Err(format!("unable to write checkpoint {} to {}: {}", step, checkpoints.path.display(), e));
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use crate::force::{kinetic_energy, potential_energy};
+use crate::global::*;
+use crate::initial::BodyState;
+use crate::io::npy_bytes;
+
+/// Rows of the diagnostics array, in the order they are stored.
+pub const DIAGNOSTICS: [&str; 5] = ["step", "time", "kinetic", "potential", "total"];
+
+/// Samples the energy every `every` steps into a `(5, k)` array with one row per entry of
+/// [`DIAGNOSTICS`], so that `step, time, kinetic, potential, total = np.load(path)` unpacks it.
+/// The header holds the sample count, so every sample rewrites the whole array into a temporary
+/// file and renames it over the last one, which keeps a complete file in place if the run stops,
+/// even mid-write. The rewrite grows with the samples taken, but stays small against the pairwise
+/// potential energy each sample costs. The potential is NaN for forces that can not tell it.
+pub struct Diagnostics {
+    pub path: PathBuf,
+    pub every: u64,
+    rows: Vec<Vec<f64>>,
+}
+
+impl Diagnostics {
+    pub fn new(path: PathBuf, every: u64) -> Self {
+        Diagnostics { path, every, rows: vec![Vec::new(); DIAGNOSTICS.len()] }
+    }
+
+    pub fn due(&self, step: u64) -> bool {
+        step % self.every == 0
+    }
+
+    pub fn write(&mut self, step: u64, bodies: &[BodyState]) -> std::io::Result<()> {
+        let kinetic = kinetic_energy(bodies);
+        let potential = potential_energy(bodies, &**FORCE);
+        let sample = [step as f64, step as f64 * ALPHA, kinetic, potential, kinetic + potential];
+        for (row, value) in self.rows.iter_mut().zip(sample.iter()) {
+            row.push(*value);
+        }
+        let tmp = self.path.with_extension("npy.tmp");
+        {
+            let mut out = BufWriter::new(File::create(&tmp)?);
+            out.write_all(&npy_bytes(&[DIAGNOSTICS.len(), self.rows[0].len()], &self.rows.concat()))?;
+            out.flush()?;
+        }
+        std::fs::rename(&tmp, &self.path)
+    }
+}
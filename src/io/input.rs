@@ -0,0 +1,172 @@
+use std::convert::TryInto;
+
+use hashbrown::HashSet;
+
+use crate::global::*;
+use crate::initial::{BodyState, inside};
+use crate::io::{HAS_ID, HAS_RADIUS, Header, HEADER_SIZE, MAGIC};
+
+/// One body read from a file, before validation.
+#[derive(Copy, Clone, Debug)]
+pub struct Record {
+    pub state: BodyState,
+    pub radius: Option<f64>,
+    pub id: Option<u64>,
+}
+
+/// Reads bodies from a CSV or binary body file, telling them apart by the binary magic.
+///
+/// Bodies are ordered by id when ids are present, must lie inside the domain and must have a
+/// non-negative mass. The engines use a fixed body radius, so per-body radii are only checked.
+pub fn load_input(path: &str) -> Result<Vec<BodyState>, String> {
+    let data = std::fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+    let mut records = if data.starts_with(MAGIC) {
+        parse_binary(&data).map_err(|e| format!("{}: {}", path, e))?
+    } else {
+        let text = String::from_utf8(data).map_err(|_| format!("{}: neither a binary body file nor utf-8 text", path))?;
+        parse_csv(&text).map_err(|e| format!("{}:{}", path, e))?
+    };
+    if records.is_empty() {
+        return Err(format!("{}: no bodies", path));
+    }
+    let mut seen = HashSet::new();
+    for (k, r) in records.iter().enumerate() {
+        if let Some(id) = r.id {
+            if !seen.insert(id) {
+                return Err(format!("{}: body {}: duplicate id {}", path, k + 1, id));
+            }
+        }
+        if !inside(r.state.x, r.state.y) {
+            return Err(format!("{}: body {}: position ({}, {}) lies outside the {} x {} domain",
+                               path, k + 1, r.state.x, r.state.y, *WIDTH / *SCALE_FACTOR, *HEIGHT / *SCALE_FACTOR));
+        }
+        if !(r.state.m >= 0.0) {
+            return Err(format!("{}: body {}: mass must be non-negative, got {}", path, k + 1, r.state.m));
+        }
+        match r.radius {
+            Some(radius) if !(radius > 0.0) =>
+                return Err(format!("{}: body {}: radius must be positive, got {}", path, k + 1, radius)),
+            _ => ()
+        }
+    }
+    if records.iter().any(|r| r.radius.map_or(false, |x| x != RADIUS)) {
+        eprintln!("{}: per-body radii are not supported, every body uses radius {}", path, RADIUS);
+    }
+    if records.iter().all(|r| r.id.is_some()) {
+        records.sort_by_key(|r| r.id);
+    }
+    Ok(records.into_iter().map(|r| r.state).collect())
+}
+
+/// Parses CSV with columns `x, y, vx, vy, m[, radius][, id]`, or any order given a header row.
+/// `mass` and `r` are accepted as aliases, `q`/`charge` is optional, and the `step` and `time`
+/// columns of snapshot files are ignored. Errors are reported as `line:column: message`.
+pub fn parse_csv(text: &str) -> Result<Vec<Record>, String> {
+    let mut columns: Vec<String> = ["x", "y", "vx", "vy", "m", "radius", "id"].iter().map(|x| x.to_string()).collect();
+    let mut records = Vec::new();
+    let mut first = true;
+    let mut header = false;
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(|x| x.trim()).collect();
+        if first {
+            first = false;
+            if fields[0].parse::<f64>().is_err() {
+                columns = fields.iter().map(|x| x.to_lowercase()).collect();
+                header = true;
+                for (k, name) in columns.iter().enumerate() {
+                    match name.as_str() {
+                        "x" | "y" | "vx" | "vy" | "m" | "mass" | "radius" | "r" | "id" | "q" | "charge" | "step" | "time" => (),
+                        _ => return Err(format!("{}:{}: unknown column `{}`", n + 1, k + 1, name)),
+                    }
+                }
+                for required in &["x", "y"] {
+                    if !columns.iter().any(|c| c == required) {
+                        return Err(format!("{}: missing column `{}`", n + 1, required));
+                    }
+                }
+                if !columns.iter().any(|c| c == "m" || c == "mass") {
+                    return Err(format!("{}: missing column `m`", n + 1));
+                }
+                continue;
+            }
+        }
+        // without a header the optional radius and id columns may be left off
+        if header && fields.len() != columns.len() {
+            return Err(format!("{}: expected {} fields as in the header, found {}", n + 1, columns.len(), fields.len()));
+        }
+        if !header && (fields.len() < 5 || fields.len() > columns.len()) {
+            return Err(format!("{}: expected 5 to {} fields, found {}", n + 1, columns.len(), fields.len()));
+        }
+        let mut record = Record {
+            state: BodyState { x: 0.0, y: 0.0, vx: 0.0, vy: 0.0, m: 0.0, q: 0.0 },
+            radius: None,
+            id: None,
+        };
+        for (k, (name, value)) in columns.iter().zip(fields.iter()).enumerate() {
+            if name == "id" {
+                record.id = Some(value.parse::<u64>()
+                    .map_err(|_| format!("{}:{}: invalid id `{}`", n + 1, k + 1, value))?);
+                continue;
+            }
+            let number = value.parse::<f64>()
+                .ok()
+                .filter(|x| x.is_finite())
+                .ok_or_else(|| format!("{}:{}: invalid number `{}` for `{}`", n + 1, k + 1, value, name))?;
+            match name.as_str() {
+                "x" => record.state.x = number,
+                "y" => record.state.y = number,
+                "vx" => record.state.vx = number,
+                "vy" => record.state.vy = number,
+                "m" | "mass" => record.state.m = number,
+                "q" | "charge" => record.state.q = number,
+                "radius" | "r" => record.radius = Some(number),
+                _ => (),
+            }
+        }
+        records.push(record);
+    }
+    Ok(records)
+}
+
+pub fn parse_binary(data: &[u8]) -> Result<Vec<Record>, String> {
+    let header = Header::from_bytes(data)?;
+    let size = header.record_size();
+    let body = &data[HEADER_SIZE..];
+    let expected = match header.count.checked_mul(size as u64) {
+        Some(x) => x,
+        None => return Err(format!("header announces {} bodies, more than any file can hold", header.count)),
+    };
+    if body.len() as u64 != expected {
+        return Err(format!("header announces {} bodies ({} bytes), found {} bytes",
+                           header.count, expected, body.len()));
+    }
+    // the count now matches the bytes actually read
+    let mut records = Vec::with_capacity(header.count as usize);
+    for (k, chunk) in body.chunks(size).enumerate() {
+        let mut offset = 0;
+        let mut next = || {
+            let bytes: [u8; 8] = chunk[offset..offset + 8].try_into().unwrap();
+            offset += 8;
+            bytes
+        };
+        let id = if header.flags & HAS_ID != 0 { Some(u64::from_le_bytes(next())) } else { None };
+        let mut values = [0.0; 5];
+        for v in values.iter_mut() {
+            *v = f64::from_le_bytes(next());
+        }
+        let radius = if header.flags & HAS_RADIUS != 0 { Some(f64::from_le_bytes(next())) } else { None };
+        if values.iter().chain(radius.iter()).any(|x| !x.is_finite()) {
+            return Err(format!("body {}: non-finite value", k + 1));
+        }
+        records.push(Record {
+            state: BodyState { x: values[0], y: values[1], vx: values[2], vy: values[3], m: values[4], q: 0.0 },
+            radius,
+            id,
+        });
+    }
+    Ok(records)
+}
@@ -0,0 +1,80 @@
+use std::convert::TryInto;
+
+pub use checkpoint::*;
+pub use diagnostics::*;
+pub use input::*;
+pub use npy::*;
+pub use snapshot::*;
+pub use trajectory::*;
+
+mod checkpoint;
+mod diagnostics;
+mod input;
+mod npy;
+mod snapshot;
+mod trajectory;
+
+/// Binary body files start with this magic, followed by the rest of [`Header`], all little-endian.
+pub const MAGIC: &[u8; 4] = b"NBDY";
+pub const VERSION: u32 = 1;
+/// Each record carries a `u64` id before its coordinates.
+pub const HAS_ID: u32 = 1;
+/// Each record carries an `f64` radius after its mass.
+pub const HAS_RADIUS: u32 = 2;
+pub const HEADER_SIZE: usize = 40;
+
+/// Header of a binary body file.
+///
+/// Layout: magic, `u32` version, `u64` body count, `u64` step, `f64` time, `u32` flags and a
+/// reserved `u32`. Records follow as `[id: u64] x y vx vy m [radius]`, with `f64` fields.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Header {
+    pub count: u64,
+    pub step: u64,
+    pub time: f64,
+    pub flags: u32,
+}
+
+impl Header {
+    pub fn record_size(&self) -> usize {
+        let mut size = 5 * 8;
+        if self.flags & HAS_ID != 0 {
+            size += 8;
+        }
+        if self.flags & HAS_RADIUS != 0 {
+            size += 8;
+        }
+        size
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_SIZE);
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.extend_from_slice(&self.count.to_le_bytes());
+        bytes.extend_from_slice(&self.step.to_le_bytes());
+        bytes.extend_from_slice(&self.time.to_le_bytes());
+        bytes.extend_from_slice(&self.flags.to_le_bytes());
+        bytes.extend_from_slice(&0_u32.to_le_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Header, String> {
+        if bytes.len() < HEADER_SIZE {
+            return Err(format!("header needs {} bytes, found {}", HEADER_SIZE, bytes.len()));
+        }
+        if &bytes[0..4] != MAGIC {
+            return Err("not a binary body file (bad magic)".to_string());
+        }
+        let version = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+        if version != VERSION {
+            return Err(format!("unsupported version {}, expected {}", version, VERSION));
+        }
+        Ok(Header {
+            count: u64::from_le_bytes(bytes[8..16].try_into().unwrap()),
+            step: u64::from_le_bytes(bytes[16..24].try_into().unwrap()),
+            time: f64::from_le_bytes(bytes[24..32].try_into().unwrap()),
+            flags: u32::from_le_bytes(bytes[32..36].try_into().unwrap()),
+        })
+    }
+}
@@ -0,0 +1,125 @@
+use std::io::{Error, ErrorKind, Write};
+
+use crate::initial::BodyState;
+
+/// Rows of the structure-of-arrays snapshot, in the order they are stored.
+pub const COLUMNS: [&str; 6] = ["x", "y", "vx", "vy", "m", "q"];
+
+/// Encodes a C-ordered `f64` array in the `.npy` format, version 1.0.
+pub fn npy_bytes(shape: &[usize], data: &[f64]) -> Vec<u8> {
+    debug_assert_eq!(shape.iter().product::<usize>(), data.len());
+    let dims = match shape {
+        [n] => format!("({},)", n),
+        _ => format!("({})", shape.iter().map(|x| x.to_string()).collect::<Vec<_>>().join(", ")),
+    };
+    let mut header = format!("{{'descr': '<f8', 'fortran_order': False, 'shape': {}, }}", dims);
+    // magic, version and length take 10 bytes; the data has to start on a 64 byte boundary
+    let total = (10 + header.len() + 1 + 63) / 64 * 64;
+    while 10 + header.len() + 1 < total {
+        header.push(' ');
+    }
+    header.push('\n');
+    let mut bytes = Vec::with_capacity(total + data.len() * 8);
+    bytes.extend_from_slice(b"\x93NUMPY\x01\x00");
+    bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(header.as_bytes());
+    for v in data {
+        bytes.extend_from_slice(&v.to_le_bytes());
+    }
+    bytes
+}
+
+/// Lays the bodies out as one row per entry of [`COLUMNS`], the way the `openmp` and `mpi`
+/// engines keep them.
+pub fn soa(bodies: &[BodyState]) -> Vec<Vec<f64>> {
+    vec![
+        bodies.iter().map(|b| b.x).collect(),
+        bodies.iter().map(|b| b.y).collect(),
+        bodies.iter().map(|b| b.vx).collect(),
+        bodies.iter().map(|b| b.vy).collect(),
+        bodies.iter().map(|b| b.m).collect(),
+        bodies.iter().map(|b| b.q).collect(),
+    ]
+}
+
+/// Writes the bodies as a single `(6, n)` array, so that
+/// `x, y, vx, vy, m, q = np.load(path)` unpacks it.
+pub fn write_npy<W: Write>(out: &mut W, bodies: &[BodyState]) -> std::io::Result<()> {
+    let data = soa(bodies).concat();
+    out.write_all(&npy_bytes(&[COLUMNS.len(), bodies.len()], &data))
+}
+
+/// Writes the bodies as an `.npz` archive with one array per column plus `step` and `time`.
+pub fn write_npz<W: Write>(out: &mut W, step: u64, time: f64, bodies: &[BodyState]) -> std::io::Result<()> {
+    let mut arrays = COLUMNS.iter()
+        .zip(soa(bodies))
+        .map(|(name, data)| (name.to_string(), npy_bytes(&[data.len()], &data)))
+        .collect::<Vec<_>>();
+    arrays.push(("step".to_string(), npy_bytes(&[], &[step as f64])));
+    arrays.push(("time".to_string(), npy_bytes(&[], &[time])));
+    write_zip(out, &arrays)
+}
+
+/// Writes `name.npy` entries into an uncompressed zip archive, which is all `np.load` needs.
+pub fn write_zip<W: Write>(out: &mut W, arrays: &[(String, Vec<u8>)]) -> std::io::Result<()> {
+    let too_large = || Error::new(ErrorKind::InvalidInput, "npz archives are limited to 4 GiB");
+    let mut central = Vec::new();
+    let mut offset = 0_u64;
+    for (name, data) in arrays {
+        let name = format!("{}.npy", name);
+        if data.len() as u64 > u32::max_value() as u64 || offset > u32::max_value() as u64 {
+            return Err(too_large());
+        }
+        let crc = crc32(data);
+        // version 2.0, no flags, stored, 1980-01-01 00:00
+        let mut common = Vec::with_capacity(26);
+        common.extend_from_slice(&20_u16.to_le_bytes());
+        common.extend_from_slice(&0_u16.to_le_bytes());
+        common.extend_from_slice(&0_u16.to_le_bytes());
+        common.extend_from_slice(&0_u16.to_le_bytes());
+        common.extend_from_slice(&33_u16.to_le_bytes());
+        common.extend_from_slice(&crc.to_le_bytes());
+        common.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        common.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        common.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        common.extend_from_slice(&0_u16.to_le_bytes());
+
+        out.write_all(&0x0403_4b50_u32.to_le_bytes())?;
+        out.write_all(&common)?;
+        out.write_all(name.as_bytes())?;
+        out.write_all(data)?;
+
+        central.extend_from_slice(&0x0201_4b50_u32.to_le_bytes());
+        central.extend_from_slice(&20_u16.to_le_bytes());
+        central.extend_from_slice(&common);
+        // no comment, disk 0, no attributes
+        central.extend_from_slice(&[0; 10]);
+        central.extend_from_slice(&(offset as u32).to_le_bytes());
+        central.extend_from_slice(name.as_bytes());
+
+        offset += (30 + name.len() + data.len()) as u64;
+    }
+    if offset > u32::max_value() as u64 {
+        return Err(too_large());
+    }
+    out.write_all(&central)?;
+    out.write_all(&0x0605_4b50_u32.to_le_bytes())?;
+    out.write_all(&[0; 4])?;
+    out.write_all(&(arrays.len() as u16).to_le_bytes())?;
+    out.write_all(&(arrays.len() as u16).to_le_bytes())?;
+    out.write_all(&(central.len() as u32).to_le_bytes())?;
+    out.write_all(&(offset as u32).to_le_bytes())?;
+    out.write_all(&0_u16.to_le_bytes())
+}
+
+/// CRC-32 as used by zip and png.
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0_u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
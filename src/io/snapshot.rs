@@ -0,0 +1,133 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use crate::global::*;
+use crate::initial::BodyState;
+use crate::io::{HAS_ID, Header, write_npy, write_npz};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Format {
+    Csv,
+    Binary,
+    Npy,
+    Npz,
+}
+
+impl Format {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Csv => "csv",
+            Format::Binary => "bin",
+            Format::Npy => "npy",
+            Format::Npz => "npz",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Format> {
+        match name {
+            "csv" => Some(Format::Csv),
+            "binary" => Some(Format::Binary),
+            "npy" => Some(Format::Npy),
+            "npz" => Some(Format::Npz),
+            _ => None
+        }
+    }
+}
+
+/// Writes the body state to `dir/snapshot_<step>.<ext>` every `every` steps.
+pub struct Snapshots {
+    pub dir: PathBuf,
+    pub every: u64,
+    pub formats: Vec<Format>,
+}
+
+impl Snapshots {
+    pub fn due(&self, step: u64) -> bool {
+        step % self.every == 0
+    }
+
+    pub fn path(&self, step: u64, format: Format) -> PathBuf {
+        self.dir.join(format!("snapshot_{:08}.{}", step, format.extension()))
+    }
+
+    pub fn write(&self, step: u64, bodies: &[BodyState]) -> std::io::Result<()> {
+        let time = step as f64 * ALPHA;
+        for format in &self.formats {
+            let mut out = BufWriter::new(File::create(self.path(step, *format))?);
+            match format {
+                Format::Csv => write_csv(&mut out, step, time, bodies)?,
+                Format::Binary => write_binary(&mut out, step, time, bodies)?,
+                Format::Npy => write_npy(&mut out, bodies)?,
+                Format::Npz => write_npz(&mut out, step, time, bodies)?,
+            }
+            out.flush()?;
+        }
+        Ok(())
+    }
+}
+
+pub fn write_csv<W: Write>(out: &mut W, step: u64, time: f64, bodies: &[BodyState]) -> std::io::Result<()> {
+    writeln!(out, "id,x,y,vx,vy,mass,step,time")?;
+    for (id, b) in bodies.iter().enumerate() {
+        writeln!(out, "{},{},{},{},{},{},{},{}", id, b.x, b.y, b.vx, b.vy, b.m, step, time)?;
+    }
+    Ok(())
+}
+
+/// Writes a binary body file with ids, which `--input` reads back.
+pub fn write_binary<W: Write>(out: &mut W, step: u64, time: f64, bodies: &[BodyState]) -> std::io::Result<()> {
+    let header = Header { count: bodies.len() as u64, step, time, flags: HAS_ID };
+    out.write_all(&header.to_bytes())?;
+    for (id, b) in bodies.iter().enumerate() {
+        out.write_all(&(id as u64).to_le_bytes())?;
+        for v in &[b.x, b.y, b.vx, b.vy, b.m] {
+            out.write_all(&v.to_le_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes whatever is due at `step`: a snapshot for `--output`, a trajectory frame for
+/// `--trajectory`, an energy sample for `--diagnostics`, an image for `--frames` and a GIF
+/// frame or SVG for `--export`. `bodies` is only called when something is actually written.
+/// Errors are left to the caller, which may have a terminal to restore.
+pub fn record<F: FnOnce() -> Vec<BodyState>>(step: u64, bodies: F) -> Result<(), String> {
+    let output = OUTPUT.as_ref().filter(|x| x.due(step));
+    let trajectory = TRAJECTORY.as_ref().filter(|x| x.lock().due(step));
+    let diagnostics = DIAGNOSTICS_OUTPUT.as_ref().filter(|x| x.lock().due(step));
+    let frames = FRAMES.as_ref().filter(|x| x.due(step));
+    let export = EXPORT.as_ref().filter(|x| x.lock().due(step));
+    if output.is_none() && trajectory.is_none() && diagnostics.is_none() && frames.is_none() && export.is_none() {
+        return Ok(());
+    }
+    let bodies = bodies();
+    if let Some(output) = output {
+        if let Err(e) = output.write(step, &bodies) {
+            return Err(format!("unable to write snapshot {} to {}: {}", step, output.dir.display(), e));
+        }
+    }
+    if let Some(trajectory) = trajectory {
+        if let Err(e) = trajectory.lock().write(step, &bodies) {
+            return Err(format!("unable to write trajectory frame {}: {}", step, e));
+        }
+    }
+    if let Some(diagnostics) = diagnostics {
+        let mut diagnostics = diagnostics.lock();
+        if let Err(e) = diagnostics.write(step, &bodies) {
+            return Err(format!("unable to write diagnostics {} to {}: {}", step, diagnostics.path.display(), e));
+        }
+    }
+    if let Some(frames) = frames {
+        if let Err(e) = frames.write(step, &bodies) {
+            return Err(format!("unable to write frame {} to {}: {}", step, frames.dir.display(), e));
+        }
+    }
+    if let Some(export) = export {
+        let mut export = export.lock();
+        if let Err(e) = export.write(step, &bodies) {
+            return Err(format!("unable to export step {} to {}: {}", step, export.path.display(), e));
+        }
+    }
+    Ok(())
+}
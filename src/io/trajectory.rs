@@ -0,0 +1,164 @@
+use std::convert::TryInto;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+
+use flate2::Compression;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+
+use crate::global::*;
+use crate::initial::BodyState;
+
+/// Trajectory files start with this magic, followed by the rest of [`TrajectoryHeader`].
+pub const TRAJECTORY_MAGIC: &[u8; 4] = b"NBTR";
+pub const TRAJECTORY_VERSION: u32 = 1;
+pub const TRAJECTORY_HEADER_SIZE: usize = 48;
+
+/// Header of a trajectory file.
+///
+/// Layout: magic, `u32` version, `u64` body count, `f64` width, `f64` height and `f64` scale of
+/// the recording window and `f64` time step, all little-endian. Frames follow as a `u64` step,
+/// a `u32` length and that many bytes of deflated `f32` `x y` pairs.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TrajectoryHeader {
+    pub count: u64,
+    pub width: f64,
+    pub height: f64,
+    pub scale: f64,
+    pub alpha: f64,
+}
+
+impl TrajectoryHeader {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(TRAJECTORY_HEADER_SIZE);
+        bytes.extend_from_slice(TRAJECTORY_MAGIC);
+        bytes.extend_from_slice(&TRAJECTORY_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&self.count.to_le_bytes());
+        for v in &[self.width, self.height, self.scale, self.alpha] {
+            bytes.extend_from_slice(&v.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<TrajectoryHeader, String> {
+        if bytes.len() < TRAJECTORY_HEADER_SIZE {
+            return Err(format!("header needs {} bytes, found {}", TRAJECTORY_HEADER_SIZE, bytes.len()));
+        }
+        if &bytes[0..4] != TRAJECTORY_MAGIC {
+            return Err("not a trajectory file (bad magic)".to_string());
+        }
+        let version = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+        if version != TRAJECTORY_VERSION {
+            return Err(format!("unsupported version {}, expected {}", version, TRAJECTORY_VERSION));
+        }
+        let f = |k: usize| f64::from_le_bytes(bytes[16 + 8 * k..24 + 8 * k].try_into().unwrap());
+        Ok(TrajectoryHeader {
+            count: u64::from_le_bytes(bytes[8..16].try_into().unwrap()),
+            width: f(0),
+            height: f(1),
+            scale: f(2),
+            alpha: f(3),
+        })
+    }
+}
+
+/// Appends one frame every `every` steps. Frames are compressed on their own, so that replay
+/// can seek in both directions.
+pub struct TrajectoryWriter {
+    file: File,
+    pub every: u64,
+    count: usize,
+}
+
+impl TrajectoryWriter {
+    pub fn create<P: AsRef<Path>>(path: P, every: u64, count: usize) -> std::io::Result<Self> {
+        let mut file = File::create(path)?;
+        let header = TrajectoryHeader {
+            count: count as u64,
+            width: *WIDTH,
+            height: *HEIGHT,
+            scale: *SCALE_FACTOR,
+            alpha: ALPHA,
+        };
+        file.write_all(&header.to_bytes())?;
+        Ok(TrajectoryWriter { file, every, count })
+    }
+
+    pub fn due(&self, step: u64) -> bool {
+        step % self.every == 0
+    }
+
+    /// Writes each frame with a single call, so a crash leaves at most one truncated frame,
+    /// which [`Trajectory::load`] drops.
+    pub fn write(&mut self, step: u64, bodies: &[BodyState]) -> std::io::Result<()> {
+        debug_assert_eq!(bodies.len(), self.count);
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::fast());
+        for b in bodies {
+            encoder.write_all(&(b.x as f32).to_le_bytes())?;
+            encoder.write_all(&(b.y as f32).to_le_bytes())?;
+        }
+        let data = encoder.finish()?;
+        let mut frame = Vec::with_capacity(12 + data.len());
+        frame.extend_from_slice(&step.to_le_bytes());
+        frame.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        frame.extend_from_slice(&data);
+        self.file.write_all(&frame)
+    }
+}
+
+/// A trajectory file held in memory, decompressing frames on demand.
+pub struct Trajectory {
+    pub header: TrajectoryHeader,
+    data: Vec<u8>,
+    /// Step and byte range of every complete frame.
+    frames: Vec<(u64, usize, usize)>,
+}
+
+impl Trajectory {
+    pub fn load(path: &str) -> Result<Trajectory, String> {
+        let data = std::fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+        let header = TrajectoryHeader::from_bytes(&data).map_err(|e| format!("{}: {}", path, e))?;
+        let mut frames = Vec::new();
+        let mut offset = TRAJECTORY_HEADER_SIZE;
+        while offset + 12 <= data.len() {
+            let step = u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+            let len = u32::from_le_bytes(data[offset + 8..offset + 12].try_into().unwrap()) as usize;
+            if offset + 12 + len > data.len() {
+                break;
+            }
+            frames.push((step, offset + 12, offset + 12 + len));
+            offset += 12 + len;
+        }
+        if offset != data.len() {
+            eprintln!("{}: dropping a truncated frame after step {}", path, frames.last().map_or(0, |x| x.0));
+        }
+        if frames.is_empty() {
+            return Err(format!("{}: no frames", path));
+        }
+        Ok(Trajectory { header, data, frames })
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn step(&self, frame: usize) -> u64 {
+        self.frames[frame].0
+    }
+
+    /// Positions of every body in `frame`.
+    pub fn positions(&self, frame: usize) -> Result<Vec<(f32, f32)>, String> {
+        let (step, from, to) = self.frames[frame];
+        let mut raw = Vec::with_capacity(self.header.count as usize * 8);
+        DeflateDecoder::new(&self.data[from..to]).read_to_end(&mut raw)
+            .map_err(|e| format!("frame at step {}: {}", step, e))?;
+        if raw.len() as u64 != self.header.count * 8 {
+            return Err(format!("frame at step {}: expected {} bodies, found {} bytes", step, self.header.count, raw.len()));
+        }
+        Ok(raw.chunks(8).map(|c| (
+            f32::from_le_bytes(c[0..4].try_into().unwrap()),
+            f32::from_le_bytes(c[4..8].try_into().unwrap()),
+        )).collect())
+    }
+}
@@ -16,6 +16,7 @@ use crate::mpi_eng::{start_mpi_child, start_mpi_root};
 use crate::openmp::start_openmp;
 use crate::pthread::start_thread_tree;
 use crate::rayon_eng::start_rayon;
+use crate::replay::start_replay;
 use crate::seq::start_tree;
 
 mod seq;
@@ -24,9 +25,15 @@ mod pthread;
 mod mpi_eng;
 mod brute_force;
 mod rayon_eng;
+mod viewer;
+mod replay;
 pub mod global;
 pub mod quad_tree;
 pub mod geometry;
+pub mod force;
+pub mod initial;
+pub mod io;
+pub mod render;
 
 fn check_thread() {
     check_mpi();
@@ -47,8 +54,62 @@ fn check_mpi() {
     }
 }
 
+fn check_multipole() {
+    if !global::FORCE.multipole() {
+        if global::WORLD.rank() == global::ROOT {
+            eprintln!("this engine needs a force law with a multipole approximation")
+        }
+        exit(0);
+    }
+}
+
+fn check_newtonian() {
+    if MATCHES.as_ref().and_then(|m| m.value_of("force")) != Some("newton") {
+        if global::WORLD.rank() == global::ROOT {
+            eprintln!("the openmp kernels only support newtonian gravity")
+        }
+        exit(0);
+    }
+}
+
+fn check_light_speed() {
+    let value = match MATCHES.as_ref().and_then(|m| m.value_of("light_speed")) {
+        Some(x) => x,
+        None => return,
+    };
+    if !value.parse::<f64>().map_or(false, |c| c.is_finite() && c > 0.0) {
+        if global::WORLD.rank() == global::ROOT {
+            eprintln!("--light-speed needs a positive speed of light, found `{}`", value)
+        }
+        exit(1);
+    }
+    if MATCHES.as_ref().and_then(|m| m.value_of("force")) != Some("newton") {
+        if global::WORLD.rank() == global::ROOT {
+            eprintln!("1PN corrections assume newtonian gravity, drop --light-speed or use --force newton")
+        }
+        exit(0);
+    }
+    match MATCHES.as_ref().and_then(|m| m.value_of("engine")) {
+        Some("brute_force") | Some("rayon") => {}
+        _ => {
+            if global::WORLD.rank() == global::ROOT {
+                eprintln!("only the brute_force and rayon engines apply 1PN corrections, drop --light-speed or switch engine")
+            }
+            exit(0);
+        }
+    }
+}
+
 pub fn main() {
     let engine = MATCHES.as_ref().and_then(|m| m.value_of("engine"));
+    if engine.is_some() && *global::REPLAY {
+        check_mpi();
+        return start_replay();
+    }
+    if engine.is_some() {
+        check_light_speed();
+        global::synchronize();
+    }
     engine.iter().for_each(|e| if global::WORLD.rank() == global::ROOT {
         print!("Name: Yifan ZHU\nStudent ID: 118010469\nAssignment 3, N-Body Simulation\n");
         println!("Engine: {}", e);
@@ -56,16 +117,35 @@ pub fn main() {
         println!("Height: {}", *global::HEIGHT);
         println!("Width: {}", *global::WIDTH);
         println!("Size: {}", *global::SIZE);
+        if *global::PINNED > 0 || *global::TRACERS > 0 {
+            println!("Pinned: {}, Tracers: {}", *global::PINNED, *global::TRACERS);
+        }
+        match (MATCHES.as_ref().and_then(|m| m.value_of("restart")), MATCHES.as_ref().and_then(|m| m.value_of("input"))) {
+            (Some(path), _) => println!("Restart: {} (step {})", path, global::step()),
+            (_, Some(path)) => println!("Input: {}", path),
+            _ => {
+                println!("Initial Conditions: {}", MATCHES.as_ref().and_then(|m| m.value_of("ic")).unwrap_or("uniform"));
+                lazy_static::initialize(&global::IC_SPEC);
+            }
+        }
+        println!("Seed: {}", *global::SEED);
+        println!("Force: {}", MATCHES.as_ref().and_then(|m| m.value_of("force")).unwrap_or("newton"));
+        global::FIELDS.iter().for_each(|f| println!("Field: {}", f));
         if *e == "openmp" || *e == "pthread" || *e == "mpi_openmp" {
             println!("Thread: {}", *global::THREAD);
         }
         if e.contains("mpi") {
             println!("Process: {}", global::WORLD.size());
         }
+        if let Some(path) = MATCHES.as_ref().and_then(|m| m.value_of("trajectory")) {
+            println!("Trajectory: {}", path);
+            lazy_static::initialize(&global::TRAJECTORY);
+        }
     });
     match engine {
         Some("tree") => {
             check_mpi();
+            check_multipole();
             start_tree();
         }
         Some("brute_force") => {
@@ -74,6 +154,7 @@ pub fn main() {
         }
         Some("openmp") => {
             check_thread();
+            check_newtonian();
             start_openmp();
         }
         Some("rayon") => {
@@ -82,10 +163,12 @@ pub fn main() {
         }
         Some("rayon_tree") => {
             check_mpi();
+            check_multipole();
             start_thread_tree(true);
         }
         Some("pthread") => {
             check_thread();
+            check_multipole();
             start_thread_tree(false);
         }
         Some("mpi_normal") => {
@@ -103,6 +186,7 @@ pub fn main() {
             }
         }
         Some("mpi_openmp") => {
+            check_newtonian();
             let world_size = global::WORLD.size() as usize;
             if world_size > *global::SIZE {
                 if global::WORLD.rank() == global::ROOT {
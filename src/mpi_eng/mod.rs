@@ -1,12 +1,13 @@
 use mpi::topology::Communicator;
 use mpi::traits::Root;
-use sdl2::event::Event;
-use sdl2::pixels::Color;
 
 use mpi_module::*;
 
 use crate::global;
+use crate::initial::BodyState;
 use crate::openmp::cpp_module::setup;
+use crate::viewer;
+use crate::viewer::Simulation;
 
 mod mpi_module;
 
@@ -26,58 +27,42 @@ fn normal_procedure(s: usize, t: usize, flag: bool, g_data: &mut GlobalData, wit
     }
 }
 
-fn benchmark_mode(with_openmp: bool) {
-    let mut g_data = GlobalData::new();
-    let world_size = global::WORLD.size() as usize;
-    let mut starts = Vec::new();
-    let mut flags = Vec::new();
-    let mut ends = Vec::new();
-    {
-        let block_size = if *global::SIZE % world_size > 0 { *global::SIZE / world_size + 1 } else { *global::SIZE / world_size };
-        let mut counter = 0;
-        let mut i = 0;
-        while counter < block_size * world_size {
-            starts.push(counter);
-            let (length, flag) = chunk_size(*global::SIZE, world_size, i);
-            ends.push(counter + length);
-            flags.push(flag);
-            counter += block_size;
-            i += 1;
+/// The root side of the run: before every step it broadcasts `false` to keep the children
+/// going, and `true` once the viewer is done.
+struct MpiRoot {
+    g_data: GlobalData,
+    s: usize,
+    t: usize,
+    flag: bool,
+    with_openmp: bool,
+    /// Rank that updates each body.
+    owners: Vec<usize>,
+}
+
+impl Simulation for MpiRoot {
+    fn prepare(&mut self) {
+        if self.with_openmp {
+            setup();
         }
     }
 
-    let mut s = 0;
-    let mut t = 0;
-    let mut flag = false;
-
-    global::ROOT_PROC.scatter_into_root(starts.as_slice(), &mut s);
-    global::ROOT_PROC.scatter_into_root(ends.as_slice(), &mut t);
-    global::ROOT_PROC.scatter_into_root(flags.as_slice(), &mut flag);
-    let mut finished = true;
-    let start = std::time::SystemTime::now();
-    normal_procedure(s, t, flag, &mut g_data, with_openmp);
-    global::ROOT_PROC.broadcast_into(&mut finished);
-    let end = std::time::SystemTime::now();
-    println!("Duration: {} ms", end.duration_since(start).unwrap().as_millis());
-}
+    fn step(&mut self) {
+        let mut finished = false;
+        global::ROOT_PROC.broadcast_into(&mut finished);
+        normal_procedure(self.s, self.t, self.flag, &mut self.g_data, self.with_openmp);
+    }
 
-pub fn start_mpi_root(with_openmp: bool) {
-    if with_openmp {
-        setup();
+    fn bodies(&self) -> Vec<BodyState> {
+        self.g_data.states()
     }
-    if *global::BENCHMARK {
-        return benchmark_mode(with_openmp);
+
+    fn owners(&self) -> Option<Vec<usize>> {
+        Some(self.owners.clone())
     }
-    let sdl_context = sdl2::init().unwrap();
-    let video_subsystem = sdl_context.video().unwrap();
-    let window = video_subsystem.window("HW3-MPI", *global::WIDTH as u32,
-                                        *global::HEIGHT as u32)
-        .position_centered()
-        .build()
-        .unwrap();
-    let mut canvas = window.into_canvas().build().unwrap();
+}
 
-    let mut g_data = GlobalData::new();
+pub fn start_mpi_root(with_openmp: bool) {
+    let g_data = GlobalData::new();
     let world_size = global::WORLD.size() as usize;
     let mut starts = Vec::new();
     let mut flags = Vec::new();
@@ -96,6 +81,13 @@ pub fn start_mpi_root(with_openmp: bool) {
         }
     }
 
+    let mut owners = vec![0; *global::SIZE];
+    for (rank, (&s, &t)) in starts.iter().zip(&ends).enumerate() {
+        for owner in &mut owners[s.min(*global::SIZE)..t.min(*global::SIZE)] {
+            *owner = rank;
+        }
+    }
+
     let mut s = 0;
     let mut t = 0;
     let mut flag = false;
@@ -103,51 +95,39 @@ pub fn start_mpi_root(with_openmp: bool) {
     global::ROOT_PROC.scatter_into_root(starts.as_slice(), &mut s);
     global::ROOT_PROC.scatter_into_root(ends.as_slice(), &mut t);
     global::ROOT_PROC.scatter_into_root(flags.as_slice(), &mut flag);
-    canvas.set_draw_color(Color::RGB(0, 255, 255));
-    canvas.clear();
-    canvas.present();
-    let mut event_pump = sdl_context.event_pump().unwrap();
-    let mut i = 0;
-    let mut n = 0;
-    let mut start = std::time::SystemTime::now();
-    let mut finished = false;
-    'running: loop {
-        n += 1;
-        canvas.set_scale(*global::SCALE_FACTOR as f32, *global::SCALE_FACTOR as f32).unwrap();
-        canvas.set_draw_color(Color::RGB(255, 255, 255));
-        canvas.clear();
-        i = (i + 1) % 255;
-        canvas.set_draw_color(Color::RGB(i, 64, 255 - i));
-        let points = g_data.to_sdl(&starts, &ends);
-        canvas.draw_points(points.as_slice()).expect("unable to draw points");
-        normal_procedure(s, t, flag, &mut g_data, with_openmp);
-        for event in event_pump.poll_iter() {
-            match event {
-                Event::Quit { .. } => {
-                    finished = true;
-                    global::ROOT_PROC.broadcast_into(&mut finished);
-                    break 'running;
-                }
-                _ => {}
-            }
-        }
-        canvas.present();
-        crate::global::show_fps(&mut n, &mut start);
-        global::ROOT_PROC.broadcast_into(&mut finished);
+    // the window would call MPI from its simulation thread, which the library may not allow
+    let refused = !*global::HEADLESS && !global::mpi_threaded();
+    if refused {
+        eprintln!("the MPI library does not support calls from other threads, \
+            run the MPI engines with --mode benchmark or record");
+    } else {
+        viewer::start("HW3-MPI", MpiRoot { g_data, s, t, flag, with_openmp, owners });
+    }
+    let mut finished = true;
+    global::ROOT_PROC.broadcast_into(&mut finished);
+    if refused {
+        std::process::exit(1);
     }
 }
 
 pub fn start_mpi_child(with_openmp: bool) {
+    if with_openmp {
+        setup();
+    }
     let mut g_data = GlobalData::new();
     let mut s = 0;
     let mut t = 0;
     let mut flag = false;
-    let mut finished = false;
     global::ROOT_PROC.scatter_into(&mut s);
     global::ROOT_PROC.scatter_into(&mut t);
     global::ROOT_PROC.scatter_into(&mut flag);
-    while !finished {
-        normal_procedure(s, t, flag, &mut g_data, with_openmp);
+    loop {
+        let mut finished = false;
         global::ROOT_PROC.broadcast_into(&mut finished);
+        if finished {
+            break;
+        }
+        normal_procedure(s, t, flag, &mut g_data, with_openmp);
+        global::advance();
     }
-}
\ No newline at end of file
+}
@@ -1,9 +1,10 @@
 use std::f64::EPSILON;
 
 use mpi::traits::*;
-use rand::Rng;
 
+use crate::force::{apply_fields, field_acceleration, Source};
 use crate::global::*;
+use crate::initial::{BodyState, generate, uniform};
 use crate::openmp::cpp_module::*;
 
 pub struct GlobalData {
@@ -14,6 +15,7 @@ pub struct GlobalData {
     gax: Vec<f64>,
     gay: Vec<f64>,
     m: Vec<f64>,
+    q: Vec<f64>,
 }
 
 
@@ -30,6 +32,7 @@ impl GlobalData {
             gax: Vec::with_capacity(size),
             gay: Vec::with_capacity(size),
             m: Vec::with_capacity(size),
+            q: Vec::with_capacity(size),
         };
         if WORLD.rank() != ROOT {
             res.m.resize(size, 0.0);
@@ -40,23 +43,27 @@ impl GlobalData {
             res.gax.resize(size, 0.0);
             res.gay.resize(size, 0.0);
             res.m.resize(size, 0.0);
+            res.q.resize(size, 0.0);
         } else {
-            let mut rng = rand::thread_rng();
-            for _ in 0..size {
-                let real_width = *WIDTH / *SCALE_FACTOR;
-                let real_height = *HEIGHT / *SCALE_FACTOR;
-                res.gx.push(rng.gen_range(0.0, real_width - RADIUS));
-                res.gy.push(rng.gen_range(0.0, real_height - RADIUS));
-                res.m.push(rng.gen_range(0.0, MASS_RANGE));
+            // bodies past SIZE only pad the last chunk; they never act on the others
+            for state in generate(*SIZE).iter().chain(uniform(size - *SIZE, &mut rand::thread_rng()).iter()) {
+                res.gx.push(state.x);
+                res.gy.push(state.y);
+                res.m.push(state.m);
+                res.q.push(state.q);
                 res.gax.push(0.0);
                 res.gay.push(0.0);
-                res.gvx.push(0.0);
-                res.gvy.push(0.0);
+                res.gvx.push(state.vx);
+                res.gvy.push(state.vy);
             }
         }
         ROOT_PROC.broadcast_into(res.m.as_mut_slice());
+        ROOT_PROC.broadcast_into(res.q.as_mut_slice());
         res
     }
+    fn source(&self, i: usize) -> Source {
+        Source { mass: self.m[i], charge: self.q[i] }
+    }
     pub fn broadcast(&mut self) {
         ROOT_PROC.broadcast_into(self.gx.as_mut_slice());
         ROOT_PROC.broadcast_into(self.gy.as_mut_slice());
@@ -66,11 +73,16 @@ impl GlobalData {
     fn update_impact(&mut self, k: usize, x_buffer: &mut Vec<f64>, y_buffer: &mut Vec<f64>, iter: usize) {
         let mut ax_acc = 0.0;
         let mut ay_acc = 0.0;
-        for i in 0..*SIZE {
+        if !kind_of(k).moves() {
+            self.gax[k] = 0.0;
+            self.gay[k] = 0.0;
+            return;
+        }
+        for i in 0..*SIZE - *TRACERS {
             if i == k { continue; } else {
                 let dist_squared = (self.gx[k] - self.gx[i]) * (self.gx[k] - self.gx[i]) + (self.gy[k] - self.gy[i]) * (self.gy[k] - self.gy[i]);
                 if dist_squared > 4.0 * RADIUS * RADIUS {
-                    let scale = G * self.m[i] / dist_squared / dist_squared.sqrt();
+                    let scale = FORCE.scale(&self.source(k), &self.source(i), dist_squared);
                     ax_acc += scale * (self.gx[i] - self.gx[k]);
                     ay_acc += scale * (self.gy[i] - self.gy[k]);
                 } else {
@@ -83,10 +95,14 @@ impl GlobalData {
                 }
             }
         }
-        self.gax[k] = ax_acc;
-        self.gay[k] = ay_acc;
+        let (fx, fy) = field_acceleration(&FIELDS, self.gx[k], self.gy[k], self.gvx[k], self.gvy[k]);
+        self.gax[k] = ax_acc + fx;
+        self.gay[k] = ay_acc + fy;
     }
     fn update_state(&mut self, i: usize) {
+        if !kind_of(i).moves() {
+            return;
+        }
         let rw: f64 = *WIDTH / *SCALE_FACTOR;
         let rh: f64 = *HEIGHT / *SCALE_FACTOR;
         if self.gvx[i].is_nan() {
@@ -101,6 +117,7 @@ impl GlobalData {
         self.gy[i] += self.gvy[i] * ALPHA + 0.5 * self.gay[i] * ALPHA * ALPHA;
         self.gvx[i] += self.gax[i] * ALPHA;
         self.gvy[i] += self.gay[i] * ALPHA;
+        DAMPING.apply(&mut self.gvx[i], &mut self.gvy[i], self.m[i], self.gx[i], self.gy[i]);
         if self.gx[i] + RADIUS >= rw {
             self.gx[i] = rw - RADIUS - EPSILON;
             self.gvx[i] = -0.5 * self.gvx[i];
@@ -123,18 +140,30 @@ impl GlobalData {
                          self.gvx.as_mut_slice(),
                          self.gvy.as_mut_slice(),
                          self.gx.as_mut_slice(),
-                         self.gy.as_mut_slice(), s, t);
+                         self.gy.as_mut_slice(), *SIZE, s, t);
         update_acc(self.m.as_slice(),
                    self.gx.as_mut_slice(),
                    self.gy.as_mut_slice(),
                    self.gax.as_mut_slice(),
-                   self.gay.as_mut_slice(), s, t);
+                   self.gay.as_mut_slice(), *SIZE, s, t);
+        apply_fields(&FIELDS,
+                     self.gx.as_slice(),
+                     self.gy.as_slice(),
+                     self.gvx.as_slice(),
+                     self.gvy.as_slice(),
+                     self.gax.as_mut_slice(),
+                     self.gay.as_mut_slice(), s, t);
         update_state(self.gx.as_mut_slice(),
                      self.gy.as_mut_slice(),
                      self.gax.as_mut_slice(),
                      self.gay.as_mut_slice(),
                      self.gvx.as_mut_slice(),
                      self.gvy.as_mut_slice(), s, t);
+        DAMPING.apply_all(self.gx.as_slice(),
+                          self.gy.as_slice(),
+                          self.gvx.as_mut_slice(),
+                          self.gvy.as_mut_slice(),
+                          self.m.as_slice(), s, t);
     }
     pub fn update_all(&mut self, s: usize, t: usize) {
         let mut x_buffer = Vec::new();
@@ -154,16 +183,16 @@ impl GlobalData {
             k += 1;
         }
     }
-    pub fn to_sdl(&self, starts: &Vec<usize>, ends: &Vec<usize>) -> Vec<sdl2::rect::Point> {
-        let mut a = Vec::new();
-        for i in 0..starts.len() {
-            for j in starts[i]..ends[i] {
-                a.push(sdl2::rect::Point::new(self.gx[j] as i32, self.gy[j] as i32))
-            }
-        }
-        a
+    pub fn states(&self) -> Vec<BodyState> {
+        (0..*SIZE).map(|i| BodyState {
+            x: self.gx[i],
+            y: self.gy[i],
+            vx: self.gvx[i],
+            vy: self.gvy[i],
+            m: self.m[i],
+            q: self.q[i],
+        }).collect()
     }
-
     pub fn gather(&mut self, s: usize, t: usize) {
         let mut buffer = Vec::with_capacity(t - s);
         buffer.resize(t - s, 0.0);
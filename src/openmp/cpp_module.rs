@@ -43,11 +43,15 @@ pub fn handle_collision(mass: &[f64],
                         vy: &mut [f64],
                         x_pos: &mut [f64],
                         y_pos: &mut [f64],
+                        count: usize,
                         from: usize,
                         to: usize,
 ) {
+    // pinned bodies never move and tracers exert nothing, so neither needs a collision response
+    let size = count - *TRACERS;
+    let from = from.max(*PINNED).min(to);
+    let to = to.min(size).max(from);
     unsafe {
-        let size = *SIZE;
         let radius = RADIUS;
         let mass = mass.as_ptr();
         let vx = vx.as_mut_ptr();
@@ -88,6 +92,7 @@ pub fn update_state(x_pos: &mut [f64],
                     from: usize,
                     to: usize,
 ) {
+    let from = from.max(*PINNED).min(to);
     unsafe {
         let radius = RADIUS;
         let width = *WIDTH / *SCALE_FACTOR;
@@ -133,11 +138,13 @@ pub fn update_acc(mass: &[f64],
                   y_pos: &mut [f64],
                   ax: &mut [f64],
                   ay: &mut [f64],
+                  count: usize,
                   from: usize,
                   to: usize,
 ) {
+    let size = count - *TRACERS;
+    let from = from.max(*PINNED).min(to);
     unsafe {
-        let size = *SIZE;
         let radius = RADIUS;
         let g = G;
         let mass = mass.as_ptr();
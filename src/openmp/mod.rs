@@ -1,114 +1,87 @@
-use rand::Rng;
-use sdl2::event::Event;
-use sdl2::pixels::Color;
-use sdl2::rect::Point;
-
+use crate::force::apply_fields;
+use crate::geometry::Kind;
 use crate::global;
-use crate::global::MASS_RANGE;
+use crate::initial;
+use crate::initial::BodyState;
 use crate::openmp::cpp_module::{handle_collision, setup, update_acc, update_state};
+use crate::viewer;
+use crate::viewer::Simulation;
 
 pub mod cpp_module;
 
-fn to_sdl(x: &[f64], y: &[f64]) -> Vec<Point> {
-    let mut a = Vec::new();
-    for i in 0..x.len() {
-        a.push(Point::new(x[i] as i32, y[i] as i32))
-    }
-    a
+#[derive(Default)]
+struct OpenMp {
+    x: Vec<f64>,
+    y: Vec<f64>,
+    vx: Vec<f64>,
+    vy: Vec<f64>,
+    ax: Vec<f64>,
+    ay: Vec<f64>,
+    m: Vec<f64>,
 }
 
-fn benchmark_mode() {
-    let mut x = Vec::new();
-    let mut y = Vec::new();
-    let mut vx = Vec::new();
-    let mut vy = Vec::new();
-    let mut ax = Vec::new();
-    let mut ay = Vec::new();
-    let mut m = Vec::new();
-    let real_width = *global::WIDTH / *global::SCALE_FACTOR;
-    let real_height = *global::HEIGHT / *global::SCALE_FACTOR;
+impl Simulation for OpenMp {
+    fn prepare(&mut self) {
+        setup();
+    }
 
-    let mut rng = rand::thread_rng();
-    for _ in 0..*global::SIZE {
-        x.push(rng.gen_range(0.0, real_width - global::RADIUS));
-        y.push(rng.gen_range(0.0, real_height - global::RADIUS));
-        m.push(rng.gen_range(0.0, MASS_RANGE));
-        ax.push(0.0);
-        ay.push(0.0);
-        vx.push(0.0);
-        vy.push(0.0);
+    fn step(&mut self) {
+        let n = self.x.len();
+        handle_collision(&self.m, &mut self.vx, &mut self.vy, &mut self.x, &mut self.y, n, 0, n);
+        update_acc(&self.m, &mut self.x, &mut self.y, &mut self.ax, &mut self.ay, n, 0, n);
+        apply_fields(&global::FIELDS, &self.x, &self.y, &self.vx, &self.vy, &mut self.ax, &mut self.ay, 0, n);
+        update_state(&mut self.x, &mut self.y, &mut self.ax, &mut self.ay, &mut self.vx, &mut self.vy, 0, n);
+        global::DAMPING.apply_all(&self.x, &self.y, &mut self.vx, &mut self.vy, &self.m, 0, n);
     }
-    let start = std::time::SystemTime::now();
-    handle_collision(&m, &mut vx, &mut vy, &mut x, &mut y, 0, *global::SIZE);
-    update_acc(&m, &mut x, &mut y, &mut ax, &mut ay, 0, *global::SIZE);
-    update_state(&mut x, &mut y, &mut ax, &mut ay, &mut vx, &mut vy, 0, *global::SIZE);
-    let end = std::time::SystemTime::now();
-    println!("Duration: {} ms", end.duration_since(start).unwrap().as_millis());
-}
 
-pub fn start_openmp() {
-    setup();
-    if *global::BENCHMARK {
-        return benchmark_mode();
+    fn bodies(&self) -> Vec<BodyState> {
+        (0..self.x.len()).map(|i| BodyState {
+            x: self.x[i],
+            y: self.y[i],
+            vx: self.vx[i],
+            vy: self.vy[i],
+            m: self.m[i],
+            q: 0.0,
+        }).collect()
     }
-    let sdl_context = sdl2::init().unwrap();
-    let video_subsystem = sdl_context.video().unwrap();
-    let window = video_subsystem.window("HW3-OpenMP", *global::WIDTH as u32,
-                                        *global::HEIGHT as u32)
-        .position_centered()
-        .build()
-        .unwrap();
-    let mut canvas = window.into_canvas().build().unwrap();
-    let mut x = Vec::new();
-    let mut y = Vec::new();
-    let mut vx = Vec::new();
-    let mut vy = Vec::new();
-    let mut ax = Vec::new();
-    let mut ay = Vec::new();
-    let mut m = Vec::new();
-    let real_width = *global::WIDTH / *global::SCALE_FACTOR;
-    let real_height = *global::HEIGHT / *global::SCALE_FACTOR;
 
-    let mut rng = rand::thread_rng();
-    for _ in 0..*global::SIZE {
-        x.push(rng.gen_range(0.0, real_width - global::RADIUS));
-        y.push(rng.gen_range(0.0, real_height - global::RADIUS));
-        m.push(rng.gen_range(0.0, MASS_RANGE));
-        ax.push(0.0);
-        ay.push(0.0);
-        vx.push(0.0);
-        vy.push(0.0);
+    /// Pinned bodies stay in front and tracers at the back, where the kernels expect them, so
+    /// new bodies go right before the tracers.
+    fn add(&mut self, body: BodyState) -> Result<(), String> {
+        let k = self.x.len() - *global::TRACERS;
+        for (v, value) in self.columns().iter_mut().zip(&[body.x, body.y, body.vx, body.vy, 0.0, 0.0, body.m]) {
+            v.insert(k, *value);
+        }
+        Ok(())
     }
 
-    canvas.set_draw_color(Color::RGB(0, 255, 255));
-    canvas.clear();
-    canvas.present();
-    let mut event_pump = sdl_context.event_pump().unwrap();
-    let mut i = 0;
-    let mut n = 0;
-    let mut start = std::time::SystemTime::now();
-    'running: loop {
-        n += 1;
-        canvas.set_scale(*global::SCALE_FACTOR as f32, *global::SCALE_FACTOR as f32).unwrap();
-        canvas.set_draw_color(Color::RGB(255, 255, 255));
-        canvas.clear();
-        i = (i + 1) % 255;
-        canvas.set_draw_color(Color::RGB(i, 64, 255 - i));
-        let points = to_sdl(x.as_slice(), y.as_slice());
-        canvas.draw_points(points.as_slice()).expect("unable to draw points");
-        handle_collision(&m, &mut vx, &mut vy, &mut x, &mut y, 0, *global::SIZE);
-        update_acc(&m, &mut x, &mut y, &mut ax, &mut ay, 0, *global::SIZE);
-        update_state(&mut x, &mut y, &mut ax, &mut ay, &mut vx, &mut vy, 0, *global::SIZE);
-        for event in event_pump.poll_iter() {
-            match event {
-                Event::Quit { .. } => {
-                    break 'running;
-                }
-                _ => {}
-            }
+    fn remove(&mut self, index: usize) -> Result<(), String> {
+        if global::kind_at(index, self.x.len()) != Kind::Dynamic {
+            return Err("only dynamic bodies can be removed".to_string());
+        }
+        for v in self.columns().iter_mut() {
+            v.remove(index);
         }
-        canvas.present();
-        global::show_fps(&mut n, &mut start);
+        Ok(())
     }
 }
 
+impl OpenMp {
+    fn columns(&mut self) -> [&mut Vec<f64>; 7] {
+        [&mut self.x, &mut self.y, &mut self.vx, &mut self.vy, &mut self.ax, &mut self.ay, &mut self.m]
+    }
+}
+
+pub fn start_openmp() {
+    let mut sim = OpenMp::default();
+    for state in initial::generate(*global::SIZE) {
+        sim.x.push(state.x);
+        sim.y.push(state.y);
+        sim.m.push(state.m);
+        sim.ax.push(0.0);
+        sim.ay.push(0.0);
+        sim.vx.push(state.vx);
+        sim.vy.push(state.vy);
+    }
+    viewer::start("HW3-OpenMP", sim);
+}
@@ -1,83 +1,87 @@
-use rand::Rng;
-use sdl2::event::Event;
-use sdl2::pixels::Color;
+use std::sync::Arc;
 
-use crate::geometry::Body;
+use crate::geometry::{Body, Kind, restore_accelerations};
 use crate::global;
-use crate::global::{MASS_RANGE, RADIUS};
+use crate::initial;
+use crate::initial::BodyState;
 use crate::pthread::pool::*;
-use std::f64::EPSILON;
+use crate::quad_tree::node::{QuadNode, TreeSnapshot};
+use crate::viewer;
+use crate::viewer::Simulation;
 
 pub mod pool;
 
-pub fn start_thread_tree(with_rayon: bool) {
-    let real_width = *global::WIDTH / *global::SCALE_FACTOR;
-    let real_height = *global::HEIGHT / *global::SCALE_FACTOR;
-    let mut body_wrappers = Vec::new();
-    let mut rng = rand::thread_rng();
-    let mut root = pool::new_root();
+struct ThreadTree {
+    body_wrappers: Vec<BodyWrapper>,
+    root: Arc<QuadNode>,
+    with_rayon: bool,
+}
 
-    for _ in 0..*global::SIZE {
-        let body = Body::new(
-            rng.gen_range(RADIUS + EPSILON, real_width),
-            rng.gen_range(RADIUS + EPSILON, real_height),
-            rng.gen_range(0.0, MASS_RANGE),
-            root.clone(),
-        );
-        body_wrappers.push(BodyWrapper::from(body));
-    }
-    if *global::BENCHMARK {
-        let start = std::time::SystemTime::now();
-        if with_rayon {
-            thread_rayon(&body_wrappers, root);
+impl Simulation for ThreadTree {
+    fn step(&mut self) {
+        self.root = if self.with_rayon {
+            thread_rayon(&self.body_wrappers, self.root.clone())
         } else {
-            thread_go(&body_wrappers, root);
-        }
-        let end = std::time::SystemTime::now();
-        println!("Duration: {} ms", end.duration_since(start).unwrap().as_millis());
-    } else {
-        let sdl_context = sdl2::init().unwrap();
-        let video_subsystem = sdl_context.video().unwrap();
-        let window = video_subsystem.window(if with_rayon { "HW3-RayonTree" } else { "HW3-PThread" }, *global::WIDTH as u32,
-                                            *global::HEIGHT as u32)
-            .position_centered()
-            .build()
-            .unwrap();
-        let mut canvas = window.into_canvas().build().unwrap();
+            thread_go(&self.body_wrappers, self.root.clone())
+        };
+    }
+
+    fn bodies(&self) -> Vec<BodyState> {
+        self.body_wrappers.iter().map(|x| x.state()).collect()
+    }
+
+    /// Rayon steals work, so only the plain thread pool has fixed owners.
+    fn owners(&self) -> Option<Vec<usize>> {
+        if self.with_rayon { None } else { Some(owners(self.body_wrappers.len())) }
+    }
+
+    /// Bodies are kicked with the acceleration the last step found.
+    fn accelerations(&self) -> Option<Vec<(f64, f64)>> {
+        Some(self.body_wrappers.iter().map(|x| x.carried()).collect())
+    }
+
+    fn tree(&self, selected: Option<usize>) -> Option<TreeSnapshot> {
+        let position = selected.map(|k| self.body_wrappers[k].position());
+        Some(TreeSnapshot::new(&self.root, position.as_ref()))
+    }
+
+    /// Inserts in front of the tracers, which keep the back of the list.
+    fn add(&mut self, body: BodyState) -> Result<(), String> {
+        let body = Body::from_state(&body, Kind::Dynamic, self.root.clone());
+        let k = self.body_wrappers.len() - *global::TRACERS;
+        self.body_wrappers.insert(k, BodyWrapper::from(body));
+        self.rebuild();
+        Ok(())
+    }
 
+    fn remove(&mut self, index: usize) -> Result<(), String> {
+        if self.body_wrappers[index].kind() != Kind::Dynamic {
+            return Err("only dynamic bodies can be removed".to_string());
+        }
+        self.body_wrappers.remove(index);
+        self.rebuild();
+        Ok(())
+    }
+}
 
-        canvas.set_draw_color(Color::RGB(0, 255, 255));
-        canvas.clear();
-        canvas.present();
-        let mut event_pump = sdl_context.event_pump().unwrap();
-        let mut i = 0;
-        let mut n = 0;
-        let mut start = std::time::SystemTime::now();
-        'running: loop {
-            n += 1;
-            canvas.set_scale(*global::SCALE_FACTOR as f32, *global::SCALE_FACTOR as f32).unwrap();
-            canvas.set_draw_color(Color::RGB(255, 255, 255));
-            canvas.clear();
-            i = (i + 1) % 255;
-            canvas.set_draw_color(Color::RGB(i, 64, 255 - i));
-            let points = body_wrappers.iter().map(|x| x.to_sdl()).collect::<Vec<_>>();
-            canvas.draw_points(points.as_slice()).expect("unable to draw points");
-            if with_rayon {
-                root = thread_rayon(&body_wrappers, root);
-            } else {
-                root = thread_go(&body_wrappers, root);
-            }
-            for event in event_pump.poll_iter() {
-                match event {
-                    Event::Quit { .. } => {
-                        break 'running;
-                    }
-                    _ => {}
-                }
-            }
-            canvas.present();
-            crate::global::show_fps(&mut n, &mut start);
+impl ThreadTree {
+    /// Builds the tree again, so that it holds exactly the bodies of the pool.
+    fn rebuild(&mut self) {
+        self.root = pool::new_root();
+        for i in &self.body_wrappers {
+            i.reinsert(self.root.clone());
         }
     }
 }
 
+pub fn start_thread_tree(with_rayon: bool) {
+    let root = pool::new_root();
+    let mut bodies = initial::generate(*global::SIZE).iter()
+        .enumerate()
+        .map(|(k, state)| Body::from_state(state, global::kind_of(k), root.clone()))
+        .collect::<Vec<_>>();
+    restore_accelerations(&mut bodies);
+    let body_wrappers = bodies.into_iter().map(BodyWrapper::from).collect();
+    let title = if with_rayon { "HW3-RayonTree" } else { "HW3-PThread" };
+    viewer::start(title, ThreadTree { body_wrappers, root, with_rayon });
+}
@@ -5,11 +5,11 @@ use std::sync::atomic::Ordering::SeqCst;
 
 use nalgebra::Vector2;
 use rayon::prelude::*;
-use sdl2::rect::Point;
 
 use crate::geometry;
-use crate::geometry::Body;
-use crate::global::{HEIGHT, SCALE_FACTOR, SIZE, THREAD, WIDTH};
+use crate::geometry::{Body, Kind, Point};
+use crate::global::{HEIGHT, SCALE_FACTOR, THREAD, WIDTH};
+use crate::initial::BodyState;
 use crate::quad_tree::node::QuadNode;
 
 struct SharedData {
@@ -33,6 +33,11 @@ fn chunk_size(total: usize, group: usize, kth: usize) -> usize {
     if a % group > 0 { a / group + 1 } else { a / group }
 }
 
+/// Index of the thread that [`thread_go`] hands each of `count` bodies to.
+pub fn owners(count: usize) -> Vec<usize> {
+    (0..*THREAD).flat_map(|i| std::iter::repeat(i).take(chunk_size(count, *THREAD, i))).collect()
+}
+
 pub struct BodyWrapper {
     ptr: Arc<RefCell<geometry::Body>>
 }
@@ -58,12 +63,24 @@ impl From<Body> for BodyWrapper {
 }
 
 impl BodyWrapper {
-    pub(crate) fn to_sdl(&self) -> sdl2::rect::Point {
-        let body = self.ptr.borrow();
-        Point::new(
-            body.position.x as i32,
-            body.position.y as i32,
-        )
+    pub(crate) fn state(&self) -> BodyState {
+        self.ptr.borrow().state()
+    }
+
+    pub(crate) fn position(&self) -> Point {
+        self.ptr.borrow().position
+    }
+
+    pub(crate) fn kind(&self) -> Kind {
+        self.ptr.borrow().kind
+    }
+
+    pub(crate) fn carried(&self) -> (f64, f64) {
+        self.ptr.borrow().carried()
+    }
+
+    pub(crate) fn reinsert(&self, root: Arc<QuadNode>) {
+        self.ptr.borrow_mut().reinsert(root);
     }
 }
 
@@ -84,7 +101,7 @@ pub fn thread_go(points: &Vec<BodyWrapper>, last_root: Arc<QuadNode>) -> Arc<Qua
     }
     for i in 0..*THREAD {
         let shared = shared.clone();
-        let work_size = chunk_size(*SIZE, *THREAD, i);
+        let work_size = chunk_size(points.len(), *THREAD, i);
         let points = (&points[counter..counter + work_size])
             .iter().map(|x| x.clone()).collect::<Vec<_>>();
         let last_root = last_root.clone();
@@ -96,7 +113,6 @@ pub fn thread_go(points: &Vec<BodyWrapper>, last_root: Arc<QuadNode>) -> Arc<Qua
                 instance.update_position();
                 instance.check_boundary();
                 instance.gravity_impact(last_root.clone());
-                instance.reinsert(shared.root.clone());
             }
             shared.finished.fetch_add(1, SeqCst);
         });
@@ -105,9 +121,19 @@ pub fn thread_go(points: &Vec<BodyWrapper>, last_root: Arc<QuadNode>) -> Arc<Qua
     while shared.finished.load(SeqCst) < *THREAD {
         std::thread::yield_now();
     }
+    reinsert(points, &shared.root);
     shared.root.clone()
 }
 
+/// Builds the next tree in the order of the bodies rather than in the order the threads
+/// finish them, so that its sums come out the same in every run and after a restart, which
+/// builds its first tree the same way.
+fn reinsert(points: &[BodyWrapper], root: &Arc<QuadNode>) {
+    for i in points {
+        i.reinsert(root.clone());
+    }
+}
+
 pub fn thread_rayon(points: &Vec<BodyWrapper>, last_root: Arc<QuadNode>) -> Arc<QuadNode> {
     crate::global::VMAP.write().clear();
     let shared = Arc::new(SharedData {
@@ -124,7 +150,8 @@ pub fn thread_rayon(points: &Vec<BodyWrapper>, last_root: Arc<QuadNode>) -> Arc<
         }
     }
 
-    points.par_iter().for_each(|i| {
+    // the old tree changes as the bodies move into it, so they go in one at a time
+    points.iter().for_each(|i| {
         let mut inst = i.ptr.borrow_mut();
         inst.make_ready();
     });
@@ -136,8 +163,8 @@ pub fn thread_rayon(points: &Vec<BodyWrapper>, last_root: Arc<QuadNode>) -> Arc<
         instance.update_position();
         instance.check_boundary();
         instance.gravity_impact(last_root.clone());
-        instance.reinsert(shared.root.clone());
     });
 
+    reinsert(points, &shared.root);
     shared.root.clone()
 }
\ No newline at end of file
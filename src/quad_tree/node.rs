@@ -1,20 +1,26 @@
 use std::collections::HashSet;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::BuildHasherDefault;
 use std::sync::{Arc, atomic::Ordering, Weak};
 use std::sync::atomic::{AtomicU8, AtomicUsize};
 use std::sync::atomic::Ordering::{Relaxed, SeqCst};
 use parking_lot::{RwLock, Mutex};
 use nalgebra::Vector2;
 
+use crate::force::Source;
 use crate::geometry::*;
 use crate::global::*;
 use std::cell::RefCell;
 
 type Ptr = Arc<QuadNode>;
+/// Hashes with fixed keys, so that the order the bodies of a node are visited in, and with it
+/// the order forces are summed in, only depends on the order they were inserted in.
+type Fixed = BuildHasherDefault<DefaultHasher>;
 
 
 pub struct QuadNode {
     region: Square,
-    objects: RwLock<HashSet<Point>>,
+    objects: RwLock<HashSet<Point, Fixed>>,
     children: [RwLock<Option<Arc<QuadNode>>>; 4],
     active: AtomicU8,
     parent: Option<Weak<QuadNode>>,
@@ -33,7 +39,7 @@ impl QuadNode {
     pub fn new(region: Square) -> Self {
         let mut res = QuadNode {
             region,
-            objects: RwLock::new(HashSet::new()),
+            objects: RwLock::new(HashSet::default()),
             children: [RwLock::new(None), RwLock::new(None), RwLock::new(None), RwLock::new(None)],
             active: AtomicU8::new(0),
             parent: None,
@@ -51,7 +57,7 @@ impl QuadNode {
     pub fn new_parented(region: Square, pa: &Ptr) -> Self {
         let mut res = QuadNode {
             region,
-            objects: RwLock::new(HashSet::new()),
+            objects: RwLock::new(HashSet::default()),
             children: [RwLock::new(None), RwLock::new(None), RwLock::new(None), RwLock::new(None)],
             active: AtomicU8::new(0),
             parent: Some(Arc::downgrade(&pa)),
@@ -315,10 +321,14 @@ fn check_limit(a: &Point, b: &Ptr) -> (bool, f64, Vector2<f64>) {
     }
 }
 
+fn source(mass: f64) -> Source {
+    Source { mass, charge: 0.0 }
+}
+
 pub(crate) fn get_impact(a: &Point, b: Ptr) -> (f64, f64) {
     if let (true, dist, center) = check_limit(a, &b) {
         unsafe {
-            let alpha = G * a.mass * (*b.mass_reader) / dist / dist.sqrt();
+            let alpha = FORCE.scale(&source(a.mass), &source(*b.mass_reader), dist);
             ((center.x - a.x) * alpha, (center.y - a.y) * alpha)
         }
     } else {
@@ -328,7 +338,7 @@ pub(crate) fn get_impact(a: &Point, b: Ptr) -> (f64, f64) {
                 let delta_x = obj.x - a.x;
                 let delta_y = obj.y - a.y;
                 let dist = delta_x * delta_x + delta_y * delta_y;
-                let alpha = G * a.mass * obj.mass / dist / dist.sqrt();
+                let alpha = FORCE.scale(&source(a.mass), &source(obj.mass), dist);
                 now.0 += delta_x * alpha;
                 now.1 += delta_y * alpha;
             }
@@ -350,3 +360,73 @@ pub(crate) fn get_impact(a: &Point, b: Ptr) -> (f64, f64) {
 }
 
 
+/// A plain copy of one node, for drawing the tree.
+#[derive(Clone, Debug)]
+pub struct NodeSnapshot {
+    pub min: (f64, f64),
+    pub max: (f64, f64),
+    pub depth: usize,
+    /// Bodies in the whole subtree.
+    pub count: usize,
+    pub mass: f64,
+    pub center: (f64, f64),
+}
+
+/// The tree an engine will use in its next step and, for a selected body, the nodes
+/// `get_impact` opens and the ones it takes as a single mass.
+#[derive(Clone, Debug, Default)]
+pub struct TreeSnapshot {
+    pub nodes: Vec<NodeSnapshot>,
+    pub opened: Vec<NodeSnapshot>,
+    pub accepted: Vec<NodeSnapshot>,
+}
+
+impl TreeSnapshot {
+    pub fn new(root: &Ptr, selected: Option<&Point>) -> Self {
+        let mut tree = TreeSnapshot::default();
+        collect(root, 0, &mut tree.nodes);
+        if let Some(a) = selected {
+            visit(a, root, 0, &mut tree.opened, &mut tree.accepted);
+        }
+        tree
+    }
+}
+
+fn node_snapshot(node: &Ptr, depth: usize) -> NodeSnapshot {
+    let mass = unsafe { *node.mass_reader };
+    let center = unsafe { *node.mass_center_reader } / mass;
+    NodeSnapshot {
+        min: (node.region.1.x, node.region.1.y),
+        max: (node.region.0.x, node.region.0.y),
+        depth,
+        count: node.size.load(Relaxed),
+        mass,
+        center: (center.x, center.y),
+    }
+}
+
+fn children(node: &Ptr) -> Vec<Ptr> {
+    let active = node.active.load(Relaxed);
+    (0..4).filter(|i| active & (1 << i) != 0)
+        .filter_map(|i| node.children[i].read().as_ref().cloned())
+        .collect()
+}
+
+fn collect(node: &Ptr, depth: usize, nodes: &mut Vec<NodeSnapshot>) {
+    nodes.push(node_snapshot(node, depth));
+    for child in children(node) {
+        collect(&child, depth + 1, nodes);
+    }
+}
+
+/// Walks the tree the way [`get_impact`] does.
+fn visit(a: &Point, node: &Ptr, depth: usize, opened: &mut Vec<NodeSnapshot>, accepted: &mut Vec<NodeSnapshot>) {
+    if check_limit(a, node).0 {
+        accepted.push(node_snapshot(node, depth));
+    } else {
+        opened.push(node_snapshot(node, depth));
+        for child in children(node) {
+            visit(a, &child, depth + 1, opened, accepted);
+        }
+    }
+}
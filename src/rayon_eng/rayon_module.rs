@@ -1,9 +1,10 @@
 use std::f64::EPSILON;
 
+use crate::force::{field_acceleration, ForceLaw, post_newtonian};
 use crate::geometry::SimpleBody;
 use crate::global::*;
 
-pub fn handle_impact(i: &SimpleBody, j: &SimpleBody, res: &mut (f64, f64, f64, f64)) {
+pub fn handle_impact<F: ForceLaw + ?Sized>(i: &SimpleBody, j: &SimpleBody, res: &mut (f64, f64, f64, f64), law: &F) {
     let delta_x = i.x - j.x;
     let delta_y = i.y - j.y;
     let dist = delta_x * delta_x + delta_y * delta_y;
@@ -16,13 +17,21 @@ pub fn handle_impact(i: &SimpleBody, j: &SimpleBody, res: &mut (f64, f64, f64, f
         res.0 -= scale * delta_x * j.m;
         res.1 -= scale * delta_y * j.m;
     } else {
-        let scale = G / dist / dist.sqrt();
-        res.2 -= delta_x * scale * j.m;
-        res.3 -= delta_y * scale * j.m;
+        let scale = law.scale(&i.source(), &j.source(), dist);
+        res.2 -= delta_x * scale;
+        res.3 -= delta_y * scale;
+        if let Some(c) = *LIGHT_SPEED {
+            let (px, py) = post_newtonian(G, c, i, j);
+            res.2 += px;
+            res.3 += py;
+        }
     }
 }
 
 pub fn update(i: &mut SimpleBody) {
+    if !i.kind.moves() {
+        return;
+    }
     let rw: f64 = *WIDTH / *SCALE_FACTOR;
     let rh: f64 = *HEIGHT / *SCALE_FACTOR;
     if i.vx.is_nan() {
@@ -33,10 +42,14 @@ pub fn update(i: &mut SimpleBody) {
         i.vy = 0.0;
         i.y = 0.618 * *HEIGHT / *SCALE_FACTOR;
     }
+    let (fx, fy) = field_acceleration(&FIELDS, i.x, i.y, i.vx, i.vy);
+    i.ax += fx;
+    i.ay += fy;
     i.x += i.vx * ALPHA + 0.5 * i.ax * ALPHA * ALPHA;
     i.y += i.vy * ALPHA + 0.5 * i.ay * ALPHA * ALPHA;
     i.vx += i.ax * ALPHA;
     i.vy += i.ay * ALPHA;
+    DAMPING.apply(&mut i.vx, &mut i.vy, i.m, i.x, i.y);
     if i.x + RADIUS >= rw {
         i.x = rw - RADIUS - EPSILON;
         i.vx = -0.5 * i.vx;
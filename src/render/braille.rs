@@ -0,0 +1,134 @@
+use crate::render::{clip, ramp, Surface};
+
+/// Dot of each braille cell bit, by row and column of the 2 × 4 cell.
+const BITS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+/// A braille character and the colour to print it in, if any.
+pub type Glyph = (char, Option<(u8, u8, u8)>);
+
+/// A grid of terminal cells, each showing 2 × 4 dots as one braille character. Every dot is a
+/// pixel of the [`Surface`], so overlays draw on it as on the window; bodies are counted per
+/// cell instead, to shade the cells by density.
+pub struct Braille {
+    pub columns: usize,
+    pub rows: usize,
+    /// Colour of each dot, if it is set.
+    dots: Vec<Option<(u8, u8, u8)>>,
+    /// Bodies in each cell and the sum of their colours.
+    counts: Vec<(u32, u32, u32, u32)>,
+    color: (u8, u8, u8),
+}
+
+impl Braille {
+    pub fn new(columns: usize, rows: usize) -> Self {
+        Braille {
+            columns,
+            rows,
+            dots: vec![None; columns * rows * 8],
+            counts: vec![(0, 0, 0, 0); columns * rows],
+            color: (255, 255, 255),
+        }
+    }
+
+    fn set(&mut self, x: i64, y: i64, color: (u8, u8, u8)) {
+        let (width, height) = (self.columns as i64 * 2, self.rows as i64 * 4);
+        if x >= 0 && y >= 0 && x < width && y < height {
+            self.dots[(y * width + x) as usize] = Some(color);
+        }
+    }
+
+    /// Sets the dot of a body and counts it in its cell with the colour `color`.
+    pub fn plot(&mut self, x: f64, y: f64, color: (u8, u8, u8)) {
+        if !(x >= 0.0 && y >= 0.0 && x < self.columns as f64 * 2.0 && y < self.rows as f64 * 4.0) {
+            return;
+        }
+        let (x, y) = (x as i64, y as i64);
+        self.set(x, y, color);
+        let cell = &mut self.counts[(y / 4) as usize * self.columns + (x / 2) as usize];
+        cell.0 += 1;
+        cell.1 += color.0 as u32;
+        cell.2 += color.1 as u32;
+        cell.3 += color.2 as u32;
+    }
+
+    /// The braille character and colour of every cell, row by row. Cells with bodies take
+    /// the mean colour of their bodies, or a colour for their density with `density` set;
+    /// other cells take the colour of the last dot drawn on them.
+    pub fn cells(&self, density: bool) -> Vec<Glyph> {
+        let width = self.columns * 2;
+        let most = self.counts.iter().map(|x| x.0).max().unwrap_or(0);
+        let mut cells = Vec::with_capacity(self.columns * self.rows);
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                let mut bits = 0;
+                let mut color = None;
+                for (dy, line) in BITS.iter().enumerate() {
+                    for (dx, bit) in line.iter().enumerate() {
+                        if let Some(c) = self.dots[(row * 4 + dy) * width + column * 2 + dx] {
+                            bits |= bit;
+                            color = Some(c);
+                        }
+                    }
+                }
+                let (n, r, g, b) = self.counts[row * self.columns + column];
+                if n > 0 {
+                    color = Some(if density {
+                        ramp((n as f64).ln_1p() / (most as f64).ln_1p())
+                    } else {
+                        ((r / n) as u8, (g / n) as u8, (b / n) as u8)
+                    });
+                }
+                cells.push((std::char::from_u32(0x2800 + bits).unwrap(), color));
+            }
+        }
+        cells
+    }
+}
+
+impl Surface for Braille {
+    fn size(&self) -> (u32, u32) {
+        (self.columns as u32 * 2, self.rows as u32 * 4)
+    }
+
+    fn set_color(&mut self, color: (u8, u8, u8)) {
+        self.color = color;
+    }
+
+    fn fill_rects(&mut self, rects: &[(i32, i32, u32, u32)]) {
+        for &(x, y, w, h) in rects {
+            for py in y as i64..y as i64 + h as i64 {
+                for px in x as i64..x as i64 + w as i64 {
+                    self.set(px, py, self.color);
+                }
+            }
+        }
+    }
+
+    /// Bresenham lines, clipped to the grid first.
+    fn draw_lines(&mut self, lines: &[(i32, i32, i32, i32)]) {
+        let (width, height) = (self.columns as i64 * 2, self.rows as i64 * 4);
+        for &line in lines {
+            let (x0, y0, x1, y1) = match clip(line, width, height) {
+                Some(x) => x,
+                None => continue,
+            };
+            let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+            let (sx, sy) = (if x0 < x1 { 1 } else { -1 }, if y0 < y1 { 1 } else { -1 });
+            let (mut x, mut y, mut err) = (x0, y0, dx + dy);
+            loop {
+                self.set(x, y, self.color);
+                if x == x1 && y == y1 {
+                    break;
+                }
+                if 2 * err >= dy {
+                    err += dy;
+                    x += sx;
+                }
+                if 2 * err <= dx {
+                    err += dx;
+                    y += sy;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use hashbrown::HashMap;
+
+use crate::global::RADIUS;
+use crate::initial::BodyState;
+
+/// Radius within which neighbours count towards the local density.
+pub const DENSITY_RADIUS: f64 = 10.0 * RADIUS;
+
+/// Body quantity that drives the colour of the bodies.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ColorBy {
+    /// The single colour cycling once per frame that the display loops always used.
+    Cycle,
+    Speed,
+    Mass,
+    Energy,
+    Density,
+    /// MPI rank or thread that updates the body.
+    Owner,
+}
+
+/// What the legend of a colouring shows.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Legend {
+    None,
+    Range(f64, f64),
+    Owners(usize),
+}
+
+pub struct Coloring {
+    pub colors: Vec<(u8, u8, u8)>,
+    pub legend: Legend,
+}
+
+impl ColorBy {
+    pub const ALL: [ColorBy; 6] = [ColorBy::Cycle, ColorBy::Speed, ColorBy::Mass, ColorBy::Energy, ColorBy::Density, ColorBy::Owner];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ColorBy::Cycle => "cycle",
+            ColorBy::Speed => "speed",
+            ColorBy::Mass => "mass",
+            ColorBy::Energy => "energy",
+            ColorBy::Density => "density",
+            ColorBy::Owner => "owner",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<ColorBy> {
+        ColorBy::ALL.iter().cloned().find(|x| x.name() == name)
+    }
+
+    pub fn next(self) -> ColorBy {
+        let k = ColorBy::ALL.iter().position(|&x| x == self).unwrap();
+        ColorBy::ALL[(k + 1) % ColorBy::ALL.len()]
+    }
+
+    /// Colours every body. Continuous quantities are scaled between their smallest and largest
+    /// value; `owners` is only used by [`ColorBy::Owner`] and `frame` by [`ColorBy::Cycle`].
+    pub fn colors(self, bodies: &[BodyState], owners: Option<&[usize]>, frame: u64) -> Coloring {
+        let values = match self {
+            ColorBy::Cycle => {
+                let i = (frame % 255) as u8;
+                return Coloring { colors: vec![(i, 64, 255 - i); bodies.len()], legend: Legend::None };
+            }
+            ColorBy::Owner => {
+                let owners = owners.map(|x| x.to_vec()).unwrap_or_else(|| vec![0; bodies.len()]);
+                let count = owners.iter().max().map_or(1, |x| x + 1);
+                return Coloring { colors: owners.iter().map(|&k| category(k)).collect(), legend: Legend::Owners(count) };
+            }
+            ColorBy::Speed => bodies.iter().map(|b| (b.vx * b.vx + b.vy * b.vy).sqrt()).collect(),
+            ColorBy::Mass => bodies.iter().map(|b| b.m).collect(),
+            ColorBy::Energy => bodies.iter().map(|b| 0.5 * b.m * (b.vx * b.vx + b.vy * b.vy)).collect(),
+            ColorBy::Density => densities(bodies),
+        };
+        let finite = values.iter().cloned().filter(|x| x.is_finite());
+        let min = finite.clone().fold(std::f64::INFINITY, f64::min);
+        let max = finite.fold(std::f64::NEG_INFINITY, f64::max);
+        let colors = values.iter()
+            .map(|v| ramp(if max > min { (v - min) / (max - min) } else { 0.5 }))
+            .collect();
+        let legend = if min <= max { Legend::Range(min, max) } else { Legend::None };
+        Coloring { colors, legend }
+    }
+}
+
+/// Number of other bodies per unit area within [`DENSITY_RADIUS`], found through a grid of
+/// cells that size.
+pub fn densities(bodies: &[BodyState]) -> Vec<f64> {
+    let cell = |b: &BodyState| ((b.x / DENSITY_RADIUS).floor() as i64, (b.y / DENSITY_RADIUS).floor() as i64);
+    let mut grid: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+    for (k, b) in bodies.iter().enumerate() {
+        grid.entry(cell(b)).or_insert_with(Vec::new).push(k);
+    }
+    let area = std::f64::consts::PI * DENSITY_RADIUS * DENSITY_RADIUS;
+    bodies.iter().enumerate().map(|(k, b)| {
+        let (cx, cy) = cell(b);
+        let mut count = 0;
+        for gx in cx - 1..=cx + 1 {
+            for gy in cy - 1..=cy + 1 {
+                for &j in grid.get(&(gx, gy)).into_iter().flatten() {
+                    let (dx, dy) = (bodies[j].x - b.x, bodies[j].y - b.y);
+                    if j != k && dx * dx + dy * dy <= DENSITY_RADIUS * DENSITY_RADIUS {
+                        count += 1;
+                    }
+                }
+            }
+        }
+        count as f64 / area
+    }).collect()
+}
+
+/// Maps `t` in `[0, 1]` from dark blue through cyan and yellow to red.
+pub fn ramp(t: f64) -> (u8, u8, u8) {
+    const STOPS: [(f64, f64, f64); 5] = [
+        (0.0, 0.0, 128.0),
+        (0.0, 128.0, 255.0),
+        (0.0, 220.0, 180.0),
+        (255.0, 220.0, 0.0),
+        (220.0, 0.0, 0.0),
+    ];
+    let t = if t.is_nan() { 0.0 } else { t.max(0.0).min(1.0) } * (STOPS.len() - 1) as f64;
+    let k = (t.floor() as usize).min(STOPS.len() - 2);
+    let f = t - k as f64;
+    let (a, b) = (STOPS[k], STOPS[k + 1]);
+    let mix = |x: f64, y: f64| (x + (y - x) * f).round() as u8;
+    (mix(a.0, b.0), mix(a.1, b.1), mix(a.2, b.2))
+}
+
+/// Well separated colours for categories, stepping the hue by the golden angle.
+pub fn category(k: usize) -> (u8, u8, u8) {
+    let hue = (k as f64 * 137.507_764) % 360.0 / 60.0;
+    let (s, v) = (0.75, 0.9);
+    let c = v * s;
+    let x = c * (1.0 - (hue % 2.0 - 1.0).abs());
+    let (r, g, b) = match hue as usize {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    let m = v - c;
+    let byte = |x: f64| ((x + m) * 255.0).round() as u8;
+    (byte(r), byte(g), byte(b))
+}
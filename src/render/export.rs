@@ -0,0 +1,51 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use crate::global::*;
+use crate::initial::BodyState;
+use crate::render::{ColorBy, GifWriter, render, write_svg};
+
+/// Target of `--export`.
+pub enum Exporter {
+    Gif(GifWriter),
+    Svg,
+}
+
+/// Exports the run every `every` steps: GIF frames are appended to one animation while the
+/// SVG snapshot is overwritten, so it ends up showing the last exported step.
+pub struct Export {
+    pub path: PathBuf,
+    pub every: u64,
+    pub color: ColorBy,
+    pub exporter: Exporter,
+}
+
+impl Export {
+    pub fn due(&self, step: u64) -> bool {
+        step % self.every == 0
+    }
+
+    pub fn write(&mut self, step: u64, bodies: &[BodyState]) -> std::io::Result<()> {
+        match &mut self.exporter {
+            Exporter::Gif(gif) => gif.write(&render(step, bodies)),
+            Exporter::Svg => {
+                let tmp = self.path.with_extension("svg.tmp");
+                {
+                    let mut out = BufWriter::new(File::create(&tmp)?);
+                    write_svg(&mut out, step, bodies, self.color)?;
+                    out.flush()?;
+                }
+                std::fs::rename(&tmp, &self.path)
+            }
+        }
+    }
+}
+
+pub fn create_export(kind: &str, path: &str, every: u64, color: ColorBy) -> std::io::Result<Export> {
+    let exporter = match kind {
+        "gif" => Exporter::Gif(GifWriter::create(path, *WIDTH as usize, *HEIGHT as usize)?),
+        _ => Exporter::Svg,
+    };
+    Ok(Export { path: path.into(), every, color, exporter })
+}
@@ -0,0 +1,62 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use crate::global::*;
+use crate::initial::BodyState;
+use crate::render::{Camera, draw_bodies, draw_legend, Raster};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FrameFormat {
+    Ppm,
+    Png,
+}
+
+impl FrameFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            FrameFormat::Ppm => "ppm",
+            FrameFormat::Png => "png",
+        }
+    }
+}
+
+/// Renders the bodies off-screen to `dir/frame_<step>.<ext>` every `every` steps.
+pub struct Frames {
+    pub dir: PathBuf,
+    pub every: u64,
+    pub format: FrameFormat,
+}
+
+impl Frames {
+    pub fn due(&self, step: u64) -> bool {
+        step % self.every == 0
+    }
+
+    pub fn path(&self, step: u64) -> PathBuf {
+        self.dir.join(format!("frame_{:08}.{}", step, self.format.extension()))
+    }
+
+    pub fn write(&self, step: u64, bodies: &[BodyState]) -> std::io::Result<()> {
+        let raster = render(step, bodies);
+        let mut out = BufWriter::new(File::create(self.path(step))?);
+        match self.format {
+            FrameFormat::Ppm => raster.write_ppm(&mut out)?,
+            FrameFormat::Png => raster.write_png(&mut out)?,
+        }
+        out.flush()
+    }
+}
+
+/// Draws the bodies the way the display loops do, with the `--color-by` and `--style` they
+/// start with. Nothing tells the renderer which engine owns a body, so `owner` shows one colour.
+pub fn render(step: u64, bodies: &[BodyState]) -> Raster {
+    let mut raster = Raster::new(*WIDTH as usize, *HEIGHT as usize);
+    raster.set_draw_color(255, 255, 255);
+    raster.clear();
+    let camera = Camera { pixels: *SCALE_FACTOR, offset: (0.0, 0.0) };
+    let coloring = COLOR_BY.colors(bodies, None, step);
+    draw_bodies(&mut raster, &camera, bodies, &coloring.colors, *STYLE);
+    draw_legend(&mut raster, *COLOR_BY, &coloring);
+    raster
+}
@@ -0,0 +1,169 @@
+use std::fs::File;
+use std::io::{Seek, SeekFrom, Write};
+use std::path::Path;
+
+use hashbrown::HashMap;
+
+use crate::render::Raster;
+
+/// Delay between GIF frames, in hundredths of a second.
+pub const GIF_DELAY: u16 = 4;
+
+/// Appends frames to an endlessly looping animated GIF, each with its own quantized palette.
+///
+/// The trailer is rewritten after every frame, so the file stays valid when the run is
+/// interrupted.
+pub struct GifWriter {
+    file: File,
+    width: usize,
+    height: usize,
+}
+
+impl GifWriter {
+    pub fn create<P: AsRef<Path>>(path: P, width: usize, height: usize) -> std::io::Result<Self> {
+        let mut file = File::create(path)?;
+        let mut head = Vec::new();
+        head.extend_from_slice(b"GIF89a");
+        head.extend_from_slice(&(width as u16).to_le_bytes());
+        head.extend_from_slice(&(height as u16).to_le_bytes());
+        // no global colour table, background 0, square pixels
+        head.extend_from_slice(&[0, 0, 0]);
+        // NETSCAPE2.0 extension, loop forever
+        head.extend_from_slice(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00");
+        head.push(0x3b);
+        file.write_all(&head)?;
+        Ok(GifWriter { file, width, height })
+    }
+
+    pub fn write(&mut self, raster: &Raster) -> std::io::Result<()> {
+        debug_assert_eq!((raster.width, raster.height), (self.width, self.height));
+        let (palette, indices) = quantize(&raster.pixels);
+        let mut frame = Vec::new();
+        // graphic control extension: no disposal, no transparency
+        frame.extend_from_slice(&[0x21, 0xf9, 0x04, 0x00]);
+        frame.extend_from_slice(&GIF_DELAY.to_le_bytes());
+        frame.extend_from_slice(&[0x00, 0x00]);
+        // image descriptor with a local colour table of 256 entries
+        frame.push(0x2c);
+        frame.extend_from_slice(&[0, 0, 0, 0]);
+        frame.extend_from_slice(&(self.width as u16).to_le_bytes());
+        frame.extend_from_slice(&(self.height as u16).to_le_bytes());
+        frame.push(0x87);
+        for k in 0..256 {
+            frame.extend_from_slice(&palette.get(k).cloned().unwrap_or([0, 0, 0]));
+        }
+        frame.push(8);
+        for block in lzw(&indices, 8).chunks(255) {
+            frame.push(block.len() as u8);
+            frame.extend_from_slice(block);
+        }
+        frame.push(0);
+        frame.push(0x3b);
+        self.file.seek(SeekFrom::End(-1))?;
+        self.file.write_all(&frame)
+    }
+}
+
+/// Reduces RGB pixels to at most 256 colours by median cut, returning the palette and one
+/// palette index per pixel.
+pub fn quantize(pixels: &[u8]) -> (Vec<[u8; 3]>, Vec<u8>) {
+    let mut histogram = HashMap::new();
+    for p in pixels.chunks(3) {
+        *histogram.entry([p[0], p[1], p[2]]).or_insert(0_u64) += 1;
+    }
+    let mut boxes = vec![histogram.into_iter().collect::<Vec<_>>()];
+    while boxes.len() < 256 {
+        let widest = boxes.iter().enumerate()
+            .filter(|(_, b)| b.len() > 1)
+            .map(|(k, b)| {
+                let range = (0..3).map(|c| {
+                    let lo = b.iter().map(|x| x.0[c]).min().unwrap();
+                    let hi = b.iter().map(|x| x.0[c]).max().unwrap();
+                    (hi - lo, c)
+                }).max().unwrap();
+                (range, k)
+            })
+            .max();
+        let ((_, channel), k) = match widest {
+            Some(x) => x,
+            None => break,
+        };
+        let mut colors = boxes.swap_remove(k);
+        colors.sort_by_key(|x| x.0[channel]);
+        let total: u64 = colors.iter().map(|x| x.1).sum();
+        let mut seen = 0;
+        let mut split = 1;
+        for (i, x) in colors.iter().enumerate().take(colors.len() - 1) {
+            seen += x.1;
+            split = i + 1;
+            if seen * 2 >= total {
+                break;
+            }
+        }
+        let rest = colors.split_off(split);
+        boxes.push(colors);
+        boxes.push(rest);
+    }
+    let mut palette = Vec::with_capacity(boxes.len());
+    let mut lookup = HashMap::new();
+    for (k, b) in boxes.iter().enumerate() {
+        let total: u64 = b.iter().map(|x| x.1).sum();
+        let mean = |c: usize| (b.iter().map(|x| x.0[c] as u64 * x.1).sum::<u64>() as f64 / total as f64).round() as u8;
+        palette.push([mean(0), mean(1), mean(2)]);
+        for x in b {
+            lookup.insert(x.0, k as u8);
+        }
+    }
+    let indices = pixels.chunks(3).map(|p| lookup[&[p[0], p[1], p[2]]]).collect();
+    (palette, indices)
+}
+
+/// Variable-length LZW as GIF uses it, starting at `min_size + 1` bits and resetting the
+/// table when it reaches 4096 codes.
+pub fn lzw(indices: &[u8], min_size: u8) -> Vec<u8> {
+    let clear = 1_u16 << min_size;
+    let end = clear + 1;
+    let mut out = Vec::new();
+    let mut buffer = 0_u32;
+    let mut bits = 0;
+    let mut emit = |code: u16, size: u8, out: &mut Vec<u8>| {
+        buffer |= (code as u32) << bits;
+        bits += size;
+        while bits >= 8 {
+            out.push(buffer as u8);
+            buffer >>= 8;
+            bits -= 8;
+        }
+    };
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next = end + 1;
+    let mut size = min_size + 1;
+    emit(clear, size, &mut out);
+    if let Some((&first, rest)) = indices.split_first() {
+        let mut current = first as u16;
+        for &k in rest {
+            if let Some(&code) = table.get(&(current, k)) {
+                current = code;
+                continue;
+            }
+            emit(current, size, &mut out);
+            if next == 4096 {
+                emit(clear, size, &mut out);
+                table.clear();
+                next = end + 1;
+                size = min_size + 1;
+            } else {
+                if next >= 1 << size {
+                    size += 1;
+                }
+                table.insert((current, k), next);
+                next += 1;
+            }
+            current = k as u16;
+        }
+        emit(current, size, &mut out);
+    }
+    emit(end, size, &mut out);
+    emit(0, 7, &mut out);
+    out
+}
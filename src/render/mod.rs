@@ -0,0 +1,33 @@
+pub use braille::*;
+pub use colormap::*;
+pub use export::*;
+pub use font::*;
+pub use frames::*;
+pub use gif::*;
+pub use motion::*;
+pub use raster::*;
+pub use scene::*;
+pub use svg::*;
+pub use tree::*;
+
+mod braille;
+mod colormap;
+mod export;
+mod font;
+mod frames;
+mod gif;
+mod motion;
+mod raster;
+mod scene;
+mod svg;
+mod tree;
+
+/// Anything the bodies can be drawn on: the SDL canvas, an off-screen [`Raster`] or the
+/// [`Braille`] dots of a terminal.
+pub trait Surface {
+    fn size(&self) -> (u32, u32);
+    fn set_color(&mut self, color: (u8, u8, u8));
+    fn fill_rects(&mut self, rects: &[(i32, i32, u32, u32)]);
+    /// Draws one-pixel lines from `(x0, y0)` to `(x1, y1)`.
+    fn draw_lines(&mut self, lines: &[(i32, i32, i32, i32)]);
+}
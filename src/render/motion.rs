@@ -0,0 +1,156 @@
+use std::collections::VecDeque;
+
+use hashbrown::HashMap;
+
+use crate::global::ALPHA;
+use crate::initial::BodyState;
+use crate::render::{Batches, Camera, Surface};
+
+/// Which vector the arrows of the display show.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Vectors {
+    None,
+    Velocity,
+    Acceleration,
+}
+
+impl Vectors {
+    pub fn next(self) -> Vectors {
+        match self {
+            Vectors::None => Vectors::Velocity,
+            Vectors::Velocity => Vectors::Acceleration,
+            Vectors::Acceleration => Vectors::None,
+        }
+    }
+}
+
+/// Trails fade towards the white background in this many shades.
+const TRAIL_SHADES: usize = 8;
+/// Arrows are scaled so that a typical one is this long, in window pixels.
+const ARROW_PIXELS: f64 = 20.0;
+
+/// What the display remembers between frames: the last positions of every body, kept in one
+/// ring buffer per body, and the velocities of the last frame to estimate accelerations.
+pub struct Motion {
+    length: usize,
+    trails: Vec<VecDeque<(f64, f64)>>,
+    velocities: Vec<(f64, f64)>,
+    accelerations: Vec<(f64, f64)>,
+    step: Option<u64>,
+}
+
+impl Motion {
+    /// Keeps at most `length` positions per body.
+    pub fn new(length: usize) -> Self {
+        Motion { length, trails: Vec::new(), velocities: Vec::new(), accelerations: Vec::new(), step: None }
+    }
+
+    pub fn clear(&mut self) {
+        self.trails.clear();
+        self.velocities.clear();
+        self.accelerations.clear();
+        self.step = None;
+    }
+
+    /// Takes the bodies shown at `step`. Frames that did not advance are ignored, while going
+    /// back in time or a change in the number of bodies starts over.
+    pub fn observe(&mut self, step: u64, bodies: &[BodyState]) {
+        match self.step {
+            Some(last) if last == step => return,
+            Some(last) if last > step || self.trails.len() != bodies.len() => self.clear(),
+            _ => {}
+        }
+        if self.trails.is_empty() {
+            self.trails = vec![VecDeque::with_capacity(self.length); bodies.len()];
+        }
+        for (trail, b) in self.trails.iter_mut().zip(bodies) {
+            if trail.len() == self.length {
+                trail.pop_front();
+            }
+            trail.push_back((b.x, b.y));
+        }
+        let velocities = bodies.iter().map(|b| (b.vx, b.vy)).collect::<Vec<_>>();
+        self.accelerations = match self.step {
+            Some(last) if self.velocities.len() == bodies.len() => {
+                let dt = (step - last) as f64 * ALPHA;
+                self.velocities.iter().zip(&velocities)
+                    .map(|(a, b)| ((b.0 - a.0) / dt, (b.1 - a.1) / dt))
+                    .collect()
+            }
+            _ => vec![(0.0, 0.0); bodies.len()],
+        };
+        self.velocities = velocities;
+        self.step = Some(step);
+    }
+
+    /// Draws the trails in the colours of their bodies, fading with age. Segments longer than
+    /// a quarter of the window come from wrapping around the domain and are left out.
+    pub fn draw_trails<S: Surface + ?Sized>(&self, surface: &mut S, camera: &Camera, colors: &[(u8, u8, u8)]) {
+        let (width, height) = surface.size();
+        let limit = (width.max(height) / 4) as i32;
+        let mut batches: Batches<(i32, i32, i32, i32)> = HashMap::new();
+        for (trail, &color) in self.trails.iter().zip(colors) {
+            let points = trail.iter()
+                .map(|&(x, y)| camera.to_window(x, y))
+                .map(|(x, y)| (x.floor() as i32, y.floor() as i32))
+                .collect::<Vec<_>>();
+            for (k, pair) in points.windows(2).enumerate() {
+                let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+                if (x1 - x0).abs() > limit || (y1 - y0).abs() > limit {
+                    continue;
+                }
+                let age = (points.len() - 2 - k) as f64 / self.length as f64;
+                let shade = (age * TRAIL_SHADES as f64) as usize as f64 / TRAIL_SHADES as f64;
+                batches.entry(fade(color, shade)).or_default().push((x0, y0, x1, y1));
+            }
+        }
+        for (color, lines) in batches {
+            surface.set_color(color);
+            surface.draw_lines(&lines);
+        }
+    }
+
+    /// Draws an arrow per body, scaled so that the median vector is [`ARROW_PIXELS`] long and
+    /// clamped at four times that.
+    pub fn draw_vectors<S: Surface + ?Sized>(&self, surface: &mut S, camera: &Camera, bodies: &[BodyState], kind: Vectors) {
+        let vectors = match kind {
+            Vectors::None => return,
+            Vectors::Velocity => &self.velocities,
+            Vectors::Acceleration => &self.accelerations,
+        };
+        let mut norms = vectors.iter().map(|v| v.0.hypot(v.1)).filter(|x| *x > 0.0 && x.is_finite()).collect::<Vec<_>>();
+        if norms.is_empty() || vectors.len() != bodies.len() {
+            return;
+        }
+        norms.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let scale = ARROW_PIXELS / norms[norms.len() / 2];
+        let mut lines = Vec::with_capacity(vectors.len() * 3);
+        for (b, v) in bodies.iter().zip(vectors) {
+            let norm = v.0.hypot(v.1);
+            if norm == 0.0 || !norm.is_finite() {
+                continue;
+            }
+            let length = (norm * scale).min(4.0 * ARROW_PIXELS);
+            let (dx, dy) = (v.0 / norm, v.1 / norm);
+            let (x0, y0) = camera.to_window(b.x, b.y);
+            let (x1, y1) = (x0 + dx * length, y0 + dy * length);
+            let head = (length / 3.0).min(6.0);
+            lines.push((x0 as i32, y0 as i32, x1 as i32, y1 as i32));
+            for &(c, s) in &[(-0.866, 0.5), (-0.866, -0.5)] {
+                let (hx, hy) = (dx * c - dy * s, dx * s + dy * c);
+                lines.push((x1 as i32, y1 as i32, (x1 + hx * head) as i32, (y1 + hy * head) as i32));
+            }
+        }
+        surface.set_color(match kind {
+            Vectors::Acceleration => (200, 0, 0),
+            _ => (0, 120, 0),
+        });
+        surface.draw_lines(&lines);
+    }
+}
+
+/// Mixes `color` with white, `amount` being the share of white.
+fn fade((r, g, b): (u8, u8, u8), amount: f64) -> (u8, u8, u8) {
+    let mix = |x: u8| (x as f64 + (255.0 - x as f64) * amount).round() as u8;
+    (mix(r), mix(g), mix(b))
+}
@@ -0,0 +1,153 @@
+use std::io::Write;
+
+use flate2::Compression;
+use flate2::write::ZlibEncoder;
+
+use crate::io::crc32;
+use crate::render::Surface;
+
+/// An off-screen RGB image that mimics the bits of the SDL canvas the display loops use.
+pub struct Raster {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+    color: (u8, u8, u8),
+}
+
+impl Raster {
+    pub fn new(width: usize, height: usize) -> Self {
+        Raster { width, height, pixels: vec![0; width * height * 3], color: (0, 0, 0) }
+    }
+
+    pub fn set_draw_color(&mut self, r: u8, g: u8, b: u8) {
+        self.color = (r, g, b);
+    }
+
+    pub fn clear(&mut self) {
+        let (r, g, b) = self.color;
+        for p in self.pixels.chunks_mut(3) {
+            p.copy_from_slice(&[r, g, b]);
+        }
+    }
+
+    /// Fills a rectangle given in window pixels, clipped to the image.
+    pub fn fill_rect(&mut self, x: i64, y: i64, w: i64, h: i64) {
+        let (x0, y0) = (x.max(0), y.max(0));
+        let (x1, y1) = ((x + w).min(self.width as i64), (y + h).min(self.height as i64));
+        let (r, g, b) = self.color;
+        for py in y0..y1 {
+            for px in x0..x1 {
+                let k = (py as usize * self.width + px as usize) * 3;
+                self.pixels[k..k + 3].copy_from_slice(&[r, g, b]);
+            }
+        }
+    }
+
+    /// Binary PPM (P6).
+    pub fn write_ppm<W: Write>(&self, out: &mut W) -> std::io::Result<()> {
+        write!(out, "P6\n{} {}\n255\n", self.width, self.height)?;
+        out.write_all(&self.pixels)
+    }
+
+    /// 8-bit RGB PNG without filtering.
+    pub fn write_png<W: Write>(&self, out: &mut W) -> std::io::Result<()> {
+        let mut ihdr = Vec::with_capacity(13);
+        ihdr.extend_from_slice(&(self.width as u32).to_be_bytes());
+        ihdr.extend_from_slice(&(self.height as u32).to_be_bytes());
+        // bit depth 8, truecolour, deflate, adaptive filtering, no interlace
+        ihdr.extend_from_slice(&[8, 2, 0, 0, 0]);
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        for row in self.pixels.chunks(self.width * 3) {
+            encoder.write_all(&[0])?;
+            encoder.write_all(row)?;
+        }
+        let idat = encoder.finish()?;
+        out.write_all(b"\x89PNG\r\n\x1a\n")?;
+        write_chunk(out, b"IHDR", &ihdr)?;
+        write_chunk(out, b"IDAT", &idat)?;
+        write_chunk(out, b"IEND", &[])
+    }
+}
+
+impl Surface for Raster {
+    fn size(&self) -> (u32, u32) {
+        (self.width as u32, self.height as u32)
+    }
+
+    fn set_color(&mut self, (r, g, b): (u8, u8, u8)) {
+        self.set_draw_color(r, g, b);
+    }
+
+    fn fill_rects(&mut self, rects: &[(i32, i32, u32, u32)]) {
+        for &(x, y, w, h) in rects {
+            self.fill_rect(x as i64, y as i64, w as i64, h as i64);
+        }
+    }
+
+    /// Bresenham lines, clipped to the image first.
+    fn draw_lines(&mut self, lines: &[(i32, i32, i32, i32)]) {
+        let (width, height) = (self.width as i64, self.height as i64);
+        for &line in lines {
+            let (x0, y0, x1, y1) = match clip(line, width, height) {
+                Some(x) => x,
+                None => continue,
+            };
+            let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+            let (sx, sy) = (if x0 < x1 { 1 } else { -1 }, if y0 < y1 { 1 } else { -1 });
+            let (mut x, mut y, mut err) = (x0, y0, dx + dy);
+            loop {
+                self.fill_rect(x, y, 1, 1);
+                if x == x1 && y == y1 {
+                    break;
+                }
+                if 2 * err >= dy {
+                    err += dy;
+                    x += sx;
+                }
+                if 2 * err <= dx {
+                    err += dx;
+                    y += sy;
+                }
+            }
+        }
+    }
+}
+
+/// Clips a line to the rectangle `[0, width) × [0, height)` by Liang-Barsky, so that drawing
+/// never walks far outside the surface.
+pub fn clip((x0, y0, x1, y1): (i32, i32, i32, i32), width: i64, height: i64) -> Option<(i64, i64, i64, i64)> {
+    let (x0, y0) = (x0 as f64, y0 as f64);
+    let (dx, dy) = (x1 as f64 - x0, y1 as f64 - y0);
+    let (mut enter, mut leave) = (0.0_f64, 1.0_f64);
+    let edges = [
+        (-dx, x0),
+        (dx, (width - 1) as f64 - x0),
+        (-dy, y0),
+        (dy, (height - 1) as f64 - y0),
+    ];
+    for &(p, q) in &edges {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None;
+            }
+        } else if p < 0.0 {
+            enter = enter.max(q / p);
+        } else {
+            leave = leave.min(q / p);
+        }
+    }
+    if enter > leave {
+        return None;
+    }
+    Some(((x0 + enter * dx).round() as i64, (y0 + enter * dy).round() as i64,
+          (x0 + leave * dx).round() as i64, (y0 + leave * dy).round() as i64))
+}
+
+fn write_chunk<W: Write>(out: &mut W, kind: &[u8; 4], data: &[u8]) -> std::io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    let mut crc_input = Vec::with_capacity(4 + data.len());
+    crc_input.extend_from_slice(kind);
+    crc_input.extend_from_slice(data);
+    out.write_all(&crc_input)?;
+    out.write_all(&crc32(&crc_input).to_be_bytes())
+}
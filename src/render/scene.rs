@@ -0,0 +1,148 @@
+use hashbrown::HashMap;
+
+use crate::global::RADIUS;
+use crate::initial::BodyState;
+use crate::render::{ColorBy, Coloring, draw_text, GLYPH_HEIGHT, Legend, ramp, category, Surface, text_width};
+
+/// Shapes to draw, grouped by colour so that each colour is set once.
+pub(crate) type Batches<T> = HashMap<(u8, u8, u8), Vec<T>>;
+
+/// How a body is drawn.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BodyStyle {
+    /// A square one simulation unit wide, like the display loops always drew.
+    Points,
+    /// A filled circle of radius [`RADIUS`].
+    Circles,
+}
+
+/// Maps simulation coordinates to window pixels.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Camera {
+    /// Window pixels per simulation unit.
+    pub pixels: f64,
+    pub offset: (f64, f64),
+}
+
+impl Camera {
+    pub fn to_window(&self, x: f64, y: f64) -> (f64, f64) {
+        ((x - self.offset.0) * self.pixels, (y - self.offset.1) * self.pixels)
+    }
+}
+
+/// Draws the bodies, batching the rectangles of each colour into one call.
+pub fn draw_bodies<S: Surface + ?Sized>(surface: &mut S, camera: &Camera, bodies: &[BodyState], colors: &[(u8, u8, u8)], style: BodyStyle) {
+    let shape = match style {
+        BodyStyle::Points => {
+            let size = camera.pixels.ceil().max(1.0) as u32;
+            vec![(0, 0, size, size)]
+        }
+        BodyStyle::Circles => spans(RADIUS * camera.pixels),
+    };
+    let (width, height) = surface.size();
+    let mut batches: Batches<(i32, i32, u32, u32)> = HashMap::new();
+    for (b, color) in bodies.iter().zip(colors) {
+        let (x, y) = camera.to_window(b.x, b.y);
+        if x < -64.0 || y < -64.0 || x > width as f64 + 64.0 || y > height as f64 + 64.0 {
+            continue;
+        }
+        let (x, y) = (x.floor() as i32, y.floor() as i32);
+        batches.entry(*color).or_default()
+            .extend(shape.iter().map(|&(dx, dy, w, h)| (x + dx, y + dy, w, h)));
+    }
+    for (color, rects) in batches {
+        surface.set_color(color);
+        surface.fill_rects(&rects);
+    }
+}
+
+/// Rows of a filled circle of `radius` pixels around the origin, at least one pixel wide.
+fn spans(radius: f64) -> Vec<(i32, i32, u32, u32)> {
+    let r = radius.max(0.5);
+    let rows = r.floor() as i32;
+    (-rows..=rows).map(|dy| {
+        let half = (r * r - (dy * dy) as f64).sqrt().round() as i32;
+        (-half, dy, (2 * half + 1) as u32, 1)
+    }).collect()
+}
+
+/// Draws the legend of `coloring` in the top right corner.
+pub fn draw_legend<S: Surface + ?Sized>(surface: &mut S, color_by: ColorBy, coloring: &Coloring) {
+    let (width, _) = surface.size();
+    let size = 1;
+    let line = GLYPH_HEIGHT * size + 2;
+    match coloring.legend {
+        Legend::None => {}
+        Legend::Range(min, max) => {
+            let labels = [color_by.name().to_string(), short(max), short(min)];
+            let text = labels.iter().map(|x| text_width(x, size)).max().unwrap();
+            let bar = 100;
+            let box_width = 12 + 6 + text + 12;
+            let left = width as i32 - box_width - 10;
+            let top = 10;
+            surface.set_color((255, 255, 255));
+            surface.fill_rects(&[(left, top, box_width as u32, (bar + 2 * line + 12) as u32)]);
+            surface.set_color((0, 0, 0));
+            draw_text(surface, left + 6, top + 4, &labels[0], size);
+            let bar_top = top + line + 6;
+            for k in 0..bar {
+                surface.set_color(ramp(1.0 - k as f64 / (bar - 1) as f64));
+                surface.fill_rects(&[(left + 6, bar_top + k, 12, 1)]);
+            }
+            surface.set_color((0, 0, 0));
+            draw_text(surface, left + 24, bar_top, &labels[1], size);
+            draw_text(surface, left + 24, bar_top + bar - GLYPH_HEIGHT * size, &labels[2], size);
+        }
+        Legend::Owners(count) => {
+            let shown = count.min(16);
+            let box_width = 12 + 6 + text_width("owner", size) + 12;
+            let left = width as i32 - box_width - 10;
+            let top = 10;
+            surface.set_color((255, 255, 255));
+            surface.fill_rects(&[(left, top, box_width as u32, (line * (shown as i32 + 1) + 10) as u32)]);
+            surface.set_color((0, 0, 0));
+            draw_text(surface, left + 6, top + 4, "owner", size);
+            for k in 0..shown {
+                let y = top + 4 + line * (k as i32 + 1);
+                surface.set_color(category(k));
+                surface.fill_rects(&[(left + 6, y, 10, 7)]);
+                surface.set_color((0, 0, 0));
+                draw_text(surface, left + 22, y, &k.to_string(), size);
+            }
+        }
+    }
+}
+
+/// Formats a legend value in at most about eight characters.
+pub fn short(v: f64) -> String {
+    if v != 0.0 && (v.abs() >= 1e4 || v.abs() < 1e-2) {
+        format!("{:.2e}", v)
+    } else {
+        format!("{:.2}", v)
+    }
+}
+
+/// The four sides of the rectangle with corners `(x0, y0)` and `(x1, y1)`.
+pub fn outline(x0: i32, y0: i32, x1: i32, y1: i32) -> [(i32, i32, i32, i32); 4] {
+    [(x0, y0, x1, y0), (x1, y0, x1, y1), (x1, y1, x0, y1), (x0, y1, x0, y0)]
+}
+
+/// Width and height in pixels of the panel [`draw_panel`] draws for `lines`.
+pub fn panel_size(lines: &[String]) -> (i32, i32) {
+    let width = lines.iter().map(|x| text_width(x, 1)).max().unwrap_or(0) + 10;
+    (width, (GLYPH_HEIGHT + 3) * lines.len() as i32 + 7)
+}
+
+/// Draws `lines` of text on a white panel with its top left corner at `(x, y)`.
+pub fn draw_panel<S: Surface + ?Sized>(surface: &mut S, x: i32, y: i32, lines: &[String]) {
+    if lines.is_empty() {
+        return;
+    }
+    let (width, height) = panel_size(lines);
+    surface.set_color((255, 255, 255));
+    surface.fill_rects(&[(x, y, width as u32, height as u32)]);
+    surface.set_color((0, 0, 0));
+    for (k, text) in lines.iter().enumerate() {
+        draw_text(surface, x + 5, y + 5 + (GLYPH_HEIGHT + 3) * k as i32, text, 1);
+    }
+}
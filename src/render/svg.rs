@@ -0,0 +1,20 @@
+use std::io::Write;
+
+use crate::global::*;
+use crate::initial::BodyState;
+use crate::render::ColorBy;
+
+/// Writes the bodies as an SVG in simulation units, one circle of radius [`RADIUS`] per body.
+pub fn write_svg<W: Write>(out: &mut W, step: u64, bodies: &[BodyState], color: ColorBy) -> std::io::Result<()> {
+    let width = *WIDTH / *SCALE_FACTOR;
+    let height = *HEIGHT / *SCALE_FACTOR;
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(out, r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}">"#,
+             *WIDTH, *HEIGHT, width, height)?;
+    writeln!(out, "<title>step {}, time {}</title>", step, step as f64 * ALPHA)?;
+    writeln!(out, r#"<rect width="{}" height="{}" fill="white"/>"#, width, height)?;
+    for (b, (r, g, bl)) in bodies.iter().zip(color.colors(bodies, None, step).colors) {
+        writeln!(out, r##"<circle cx="{:.3}" cy="{:.3}" r="{}" fill="#{:02x}{:02x}{:02x}"/>"##, b.x, b.y, RADIUS, r, g, bl)?;
+    }
+    writeln!(out, "</svg>")
+}
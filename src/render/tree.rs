@@ -0,0 +1,61 @@
+use crate::quad_tree::node::{NodeSnapshot, TreeSnapshot};
+use crate::render::{Camera, category, ramp, Surface};
+
+/// How the quad tree overlay colours the node regions.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TreeColor {
+    Plain,
+    Depth,
+    /// By the logarithm of the number of bodies in the subtree.
+    Count,
+}
+
+impl TreeColor {
+    pub fn parse(name: &str) -> Option<TreeColor> {
+        match name {
+            "plain" => Some(TreeColor::Plain),
+            "depth" => Some(TreeColor::Depth),
+            "count" => Some(TreeColor::Count),
+            _ => None,
+        }
+    }
+}
+
+/// Outlines every node region and marks the mass centres. With `highlight`, the nodes a
+/// selected body opens are outlined in orange and the ones it takes as a whole in green.
+pub fn draw_tree<S: Surface + ?Sized>(surface: &mut S, camera: &Camera, tree: &TreeSnapshot, color: TreeColor, highlight: bool) {
+    let max_count = tree.nodes.iter().map(|x| x.count).max().unwrap_or(1).max(2) as f64;
+    for node in &tree.nodes {
+        surface.set_color(match color {
+            TreeColor::Plain => (160, 160, 160),
+            TreeColor::Depth => category(node.depth),
+            TreeColor::Count => ramp((node.count.max(1) as f64).ln() / max_count.ln()),
+        });
+        surface.draw_lines(&outline(camera, node, 0));
+    }
+    surface.set_color((0, 0, 0));
+    let centers = tree.nodes.iter()
+        .filter(|x| x.mass > 0.0 && x.center.0.is_finite() && x.center.1.is_finite())
+        .map(|x| camera.to_window(x.center.0, x.center.1))
+        .map(|(x, y)| (x as i32 - 1, y as i32 - 1, 3, 3))
+        .collect::<Vec<_>>();
+    surface.fill_rects(&centers);
+    if highlight {
+        for (nodes, color) in &[(&tree.opened, (255, 140, 0)), (&tree.accepted, (0, 170, 0))] {
+            surface.set_color(*color);
+            for node in nodes.iter() {
+                surface.draw_lines(&outline(camera, node, 0));
+                surface.draw_lines(&outline(camera, node, 1));
+            }
+        }
+    }
+}
+
+/// The four sides of a node region, moved `inset` pixels inwards.
+fn outline(camera: &Camera, node: &NodeSnapshot, inset: i32) -> [(i32, i32, i32, i32); 4] {
+    let (x0, y0) = camera.to_window(node.min.0, node.min.1);
+    let (x1, y1) = camera.to_window(node.max.0, node.max.1);
+    let (x0, y0) = (x0 as i32 + inset, y0 as i32 + inset);
+    let (x1, y1) = (x1 as i32 - inset, y1 as i32 - inset);
+    [(x0, y0, x1, y0), (x1, y0, x1, y1), (x1, y1, x0, y1), (x0, y1, x0, y0)]
+}
@@ -0,0 +1,100 @@
+use std::time::Instant;
+
+use crate::global;
+use crate::initial::BodyState;
+use crate::io::Trajectory;
+use crate::render::Motion;
+use crate::viewer::{DEFAULT_TRAILS, Hud, inspect, MAX_SPEED, MIN_SPEED, observe, open, pace, Scene};
+
+/// Plays a recorded trajectory back through the shared viewer without touching the physics,
+/// with the replay-only controls enabled.
+pub fn start_replay() {
+    let path = global::MATCHES.as_ref().and_then(|m| m.value_of("trajectory")).unwrap();
+    let trajectory = match Trajectory::load(path) {
+        Ok(x) => x,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let header = trajectory.header;
+    let last = trajectory.frame_count() - 1;
+    println!("Replay: {} ({} bodies, {} frames, steps {} to {})",
+             path, header.count, trajectory.frame_count(), trajectory.step(0), trajectory.step(last));
+
+    let (mut frontend, mut view) = open("HW3-Replay", header.width as f64, header.height as f64, header.scale);
+    let mut hud = Hud::new(format!("replay of {}", path));
+    let mut motion = Motion::new(if *global::TRAILS > 0 { *global::TRAILS } else { DEFAULT_TRAILS });
+    view.reversible = true;
+    view.reversed = *global::REPLAY_SPEED < 0.0;
+    // within the range of the speed keys, which could not bring a zero speed back
+    view.speed = global::REPLAY_SPEED.abs().max(MIN_SPEED).min(MAX_SPEED);
+    let mut frame = if view.reversed { last } else { 0 };
+    let mut shown = None;
+    let mut bodies = Vec::new();
+    let mut n = 0;
+    let mut start = std::time::SystemTime::now();
+    while !view.quit {
+        let begin = Instant::now();
+        n += 1;
+        if shown != Some(frame) {
+            bodies = match hud.measure("load", || load(&trajectory, frame)) {
+                Ok(x) => x,
+                Err(e) => {
+                    // gives the terminal back before exiting
+                    drop(frontend);
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            };
+            shown = Some(frame);
+        }
+        view.track(&bodies, frontend.size());
+        observe(&view, &mut motion, trajectory.step(frame), &bodies);
+        // replayed bodies carry no masses, so there is no energy to show
+        let lines = if view.hud { Some(hud.lines(trajectory.step(frame), &bodies, false)) } else { None };
+        let boxed = inspect(&bodies, &view.boxed);
+        hud.measure("draw", || frontend.draw(&view, &Scene {
+            bodies: &bodies,
+            owners: None,
+            motion: &motion,
+            tree: None,
+            hud: lines.as_ref().map(|x| x.as_slice()),
+            inspect: &boxed,
+        }));
+        for command in frontend.poll() {
+            view.apply(command);
+        }
+        view.resolve(&bodies);
+        // a replay has no engine to edit
+        view.edits.clear();
+        match view.jump.take() {
+            Some(true) => frame = last,
+            Some(false) => frame = 0,
+            None => {}
+        }
+        let delta = view.advance();
+        frame = (frame as i64 + delta).max(0).min(last as i64) as usize;
+        if delta == 0 {
+            std::thread::sleep(std::time::Duration::from_millis(15));
+        }
+        hud.frame();
+        global::show_fps(&mut n, &mut start);
+        pace(begin, frontend.frame_time());
+    }
+}
+
+/// The bodies of `frame`. Trajectories only hold positions, so velocities are estimated from
+/// the frame before.
+fn load(trajectory: &Trajectory, frame: usize) -> Result<Vec<BodyState>, String> {
+    let positions = trajectory.positions(frame)?;
+    let previous = if frame > 0 { Some(trajectory.positions(frame - 1)?) } else { None };
+    let dt = if frame > 0 { (trajectory.step(frame) - trajectory.step(frame - 1)) as f64 * global::ALPHA } else { 1.0 };
+    Ok(positions.iter().enumerate().map(|(k, &(x, y))| {
+        let (vx, vy) = match &previous {
+            Some(p) => ((x - p[k].0) as f64 / dt, (y - p[k].1) as f64 / dt),
+            None => (0.0, 0.0),
+        };
+        BodyState { x: x as f64, y: y as f64, vx, vy, m: 0.0, q: 0.0 }
+    }).collect())
+}
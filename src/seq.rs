@@ -1,16 +1,16 @@
 use std::sync::Arc;
 
 use nalgebra::Vector2;
-use rand::Rng;
-use sdl2::event::Event;
-use sdl2::pixels::Color;
 
 use crate::geometry;
-use crate::geometry::{Body, Square};
+use crate::geometry::{Body, Kind, Square};
 use crate::global;
+use crate::initial;
+use crate::initial::BodyState;
 use crate::quad_tree;
-use crate::quad_tree::node::QuadNode;
-use std::f64::EPSILON;
+use crate::quad_tree::node::{QuadNode, TreeSnapshot};
+use crate::viewer;
+use crate::viewer::Simulation;
 
 fn refresh(pool: &mut Vec<Body>, root: &mut Arc<QuadNode>, boundary: &Square) {
     {
@@ -37,71 +37,72 @@ fn refresh(pool: &mut Vec<Body>, root: &mut Arc<QuadNode>, boundary: &Square) {
     }
 }
 
-pub fn start_tree() {
-    let real_width = *global::WIDTH / *global::SCALE_FACTOR;
-    let real_height = *global::HEIGHT / *global::SCALE_FACTOR;
-    let boundary = geometry::Square(
-        Vector2::new(real_width, real_height),
-        Vector2::new(0.0, 0.0)
-    );
+struct Tree {
+    pool: Vec<Body>,
+    root: Arc<QuadNode>,
+    boundary: Square,
+}
+
+impl Simulation for Tree {
+    fn step(&mut self) {
+        refresh(&mut self.pool, &mut self.root, &self.boundary);
+    }
 
-    let mut root = Arc::new(quad_tree::node::QuadNode::new(boundary.clone()));
-    let mut rng = rand::thread_rng();
-    let mut pool = Vec::new();
-    for _ in 0..*global::SIZE {
-        pool.push(geometry::Body::new(
-            rng.gen_range(global::RADIUS + EPSILON, real_width - global::RADIUS),
-            rng.gen_range(global::RADIUS + EPSILON, real_height - global::RADIUS),
-            rng.gen_range(0.0, 20.0),
-            root.clone(),
-        ));
+    fn bodies(&self) -> Vec<BodyState> {
+        self.pool.iter().map(|x| x.state()).collect()
     }
-    if *global::BENCHMARK {
-        let start = std::time::SystemTime::now();
-        refresh(&mut pool, &mut root, &boundary);
-        let end = std::time::SystemTime::now();
-        println!("Duration: {} ms", end.duration_since(start).unwrap().as_millis());
-    } else {
-        let sdl_context = sdl2::init().unwrap();
-        let video_subsystem = sdl_context.video().unwrap();
-        let window = video_subsystem.window("HW3-Sequential", *global::WIDTH as u32,
-                                            *global::HEIGHT as u32)
-            .position_centered()
-            .build()
-            .unwrap();
 
-        let mut canvas = window.into_canvas().build().unwrap();
+    /// Bodies are kicked with the acceleration the last step found.
+    fn accelerations(&self) -> Option<Vec<(f64, f64)>> {
+        Some(self.pool.iter().map(|x| x.carried()).collect())
+    }
 
-        canvas.set_draw_color(Color::RGB(0, 255, 255));
-        canvas.clear();
-        canvas.present();
-        let mut event_pump = sdl_context.event_pump().unwrap();
-        let mut i = 0;
-        let mut n = 0;
-        let mut start = std::time::SystemTime::now();
-        'running: loop {
-            n += 1;
-            //println!("{:?}", pool);
-            canvas.set_scale(*global::SCALE_FACTOR as f32, *global::SCALE_FACTOR as f32).unwrap();
-            canvas.set_draw_color(Color::RGB(255, 255, 255));
-            canvas.clear();
-            i = (i + 1) % 255;
-            canvas.set_draw_color(Color::RGB(i, 64, 255 - i));
-            let points = pool.iter().map(|x| x.geometric()).collect::<Vec<_>>();
-            canvas.draw_points(points.as_slice()).expect("unable to draw points");
+    fn tree(&self, selected: Option<usize>) -> Option<TreeSnapshot> {
+        Some(TreeSnapshot::new(&self.root, selected.map(|k| &self.pool[k].position)))
+    }
+
+    /// Inserts in front of the tracers, which keep the back of the list.
+    fn add(&mut self, body: BodyState) -> Result<(), String> {
+        let k = self.pool.len() - *global::TRACERS;
+        self.pool.insert(k, Body::from_state(&body, Kind::Dynamic, self.root.clone()));
+        self.rebuild();
+        Ok(())
+    }
 
-            for event in event_pump.poll_iter() {
-                match event {
-                    Event::Quit { .. } => {
-                        break 'running;
-                    }
-                    _ => {}
-                }
-            }
+    fn remove(&mut self, index: usize) -> Result<(), String> {
+        if self.pool[index].kind != Kind::Dynamic {
+            return Err("only dynamic bodies can be removed".to_string());
+        }
+        self.pool.remove(index);
+        self.rebuild();
+        Ok(())
+    }
+}
 
-            canvas.present();
-            refresh(&mut pool, &mut root, &boundary);
-            global::show_fps(&mut n, &mut start);
+impl Tree {
+    /// Builds the tree again, so that it holds exactly the bodies of the pool.
+    fn rebuild(&mut self) {
+        self.root = Arc::new(QuadNode::new(self.boundary));
+        for i in &mut self.pool {
+            i.reinsert(self.root.clone());
         }
     }
-}
\ No newline at end of file
+}
+
+pub fn start_tree() {
+    let real_width = *global::WIDTH / *global::SCALE_FACTOR;
+    let real_height = *global::HEIGHT / *global::SCALE_FACTOR;
+    let boundary = geometry::Square(
+        Vector2::new(real_width, real_height),
+        Vector2::new(0.0, 0.0)
+    );
+
+    let root = Arc::new(quad_tree::node::QuadNode::new(boundary.clone()));
+    let pool = initial::generate(*global::SIZE);
+    let mut pool = pool.iter()
+        .enumerate()
+        .map(|(k, state)| Body::from_state(state, global::kind_of(k), root.clone()))
+        .collect::<Vec<_>>();
+    geometry::restore_accelerations(&mut pool);
+    viewer::start("HW3-Sequential", Tree { pool, root, boundary });
+}
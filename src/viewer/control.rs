@@ -0,0 +1,380 @@
+use crate::global::{ALPHA, COLOR_BY, HUD, QUAD_TREE, STYLE, TRACK, TRAILS, VECTORS};
+use crate::initial::BodyState;
+use crate::render::{BodyStyle, Camera, ColorBy, TreeColor, Vectors};
+
+/// Slowest and fastest playback, in simulation steps per displayed frame.
+pub const MIN_SPEED: f64 = 1.0 / 64.0;
+pub const MAX_SPEED: f64 = 256.0;
+pub const ZOOM_STEP: f64 = 1.1;
+/// How far from a body, in window pixels, a click still selects it.
+pub const PICK_PIXELS: f64 = 10.0;
+/// A spawned body would cover its drag in this many steps at its initial velocity.
+pub const SPAWN_STEPS: f64 = 100.0;
+pub const MASS_STEP: f64 = 1.25;
+/// Share of the window the bounding box of the bodies takes when fitted.
+pub const FIT_MARGIN: f64 = 0.9;
+
+/// What the camera keeps in view.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Tracking {
+    /// The camera stays where the user put it.
+    Off,
+    /// Keeps the centre of mass in the middle of the window.
+    Center,
+    /// Keeps the most massive body in the middle of the window.
+    Heaviest,
+    /// Keeps the selected body in the middle of the window.
+    Selected,
+    /// Zooms and pans so that every body is in the window.
+    Fit,
+}
+
+impl Tracking {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "off" => Some(Tracking::Off),
+            "com" => Some(Tracking::Center),
+            "heaviest" => Some(Tracking::Heaviest),
+            "selected" => Some(Tracking::Selected),
+            "fit" => Some(Tracking::Fit),
+            _ => None,
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            Tracking::Off => Tracking::Center,
+            Tracking::Center => Tracking::Heaviest,
+            Tracking::Heaviest => Tracking::Selected,
+            Tracking::Selected => Tracking::Fit,
+            Tracking::Fit => Tracking::Off,
+        }
+    }
+}
+
+/// Input from any front end, already stripped of device details.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Command {
+    Quit,
+    TogglePause,
+    /// Runs exactly one step while paused.
+    Step,
+    /// Goes back one step, in replays only.
+    StepBack,
+    /// Flips the playback direction, in replays only.
+    Reverse,
+    /// Jumps to the start or the end, in replays only.
+    First,
+    Last,
+    Faster,
+    Slower,
+    /// Zooms by `factor` keeping the window point `(x, y)` in place.
+    Zoom { factor: f64, x: f64, y: f64 },
+    /// Moves the view by a distance in window pixels.
+    Pan { dx: f64, dy: f64 },
+    ResetView,
+    /// Switches to the next colour map.
+    NextColors,
+    /// Switches between points and circles.
+    ToggleStyle,
+    ToggleTrails,
+    /// Switches between no arrows, velocities and accelerations.
+    NextVectors,
+    /// Switches between no quad tree and its colourings.
+    NextTree,
+    /// Toggles highlighting the nodes the selected body opens.
+    ToggleOpened,
+    ToggleHud,
+    /// Switches to the next camera tracking mode.
+    NextTracking,
+    /// Selects the body nearest to the window point `(x, y)`, if any is close.
+    Select { x: f64, y: f64 },
+    /// Adds a body at the window point `(x, y)`, moving along the drag `(dx, dy)`.
+    Spawn { x: f64, y: f64, dx: f64, dy: f64 },
+    /// Scales the mass of the bodies to spawn.
+    SpawnMass { factor: f64 },
+    /// Removes the body nearest to the window point `(x, y)`, if any is close.
+    Delete { x: f64, y: f64 },
+    /// Selects every body in a window rectangle for inspection.
+    BoxSelect { x0: f64, y0: f64, x1: f64, y1: f64 },
+}
+
+/// A change of the bodies asked for in the window, applied by the engine between two steps.
+#[derive(Copy, Clone, Debug)]
+pub enum Edit {
+    Add(BodyState),
+    /// Index into the bodies the window showed.
+    Remove(usize),
+}
+
+/// Camera and playback state shared by every front end.
+///
+/// Window coordinates are `(world - offset) * scale * zoom`, with `scale` the `--scale` of the
+/// run, so an untouched view shows what the display loops always showed.
+#[derive(Clone, Debug)]
+pub struct View {
+    pub scale: f64,
+    pub zoom: f64,
+    pub offset: (f64, f64),
+    pub paused: bool,
+    pub speed: f64,
+    pub quit: bool,
+    /// Whether the direction can change, which only holds for replays.
+    pub reversible: bool,
+    pub reversed: bool,
+    /// Set by [`Command::First`] and [`Command::Last`] until the replay takes it.
+    pub jump: Option<bool>,
+    pub color_by: ColorBy,
+    pub style: BodyStyle,
+    pub trails: bool,
+    pub vectors: Vectors,
+    pub tree: Option<TreeColor>,
+    pub opened: bool,
+    pub hud: bool,
+    pub tracking: Tracking,
+    pub selected: Option<usize>,
+    /// Bodies in the inspection box.
+    pub boxed: Vec<usize>,
+    /// Mass of spawned bodies, set to the mean mass by the first [`View::resolve`] so that the
+    /// wheel has something to scale.
+    pub spawn_mass: Option<f64>,
+    /// Edits for the engine to take.
+    pub edits: Vec<Edit>,
+    /// Commands that need the bodies on screen, kept until [`View::resolve`].
+    queued: Vec<Command>,
+    pending: f64,
+    single: i64,
+}
+
+impl View {
+    pub fn new(scale: f64) -> Self {
+        View {
+            scale,
+            zoom: 1.0,
+            offset: (0.0, 0.0),
+            paused: false,
+            speed: 1.0,
+            quit: false,
+            reversible: false,
+            reversed: false,
+            jump: None,
+            color_by: *COLOR_BY,
+            style: *STYLE,
+            trails: *TRAILS > 0,
+            vectors: *VECTORS,
+            tree: *QUAD_TREE,
+            opened: true,
+            hud: *HUD,
+            tracking: *TRACK,
+            selected: None,
+            boxed: Vec::new(),
+            spawn_mass: None,
+            edits: Vec::new(),
+            queued: Vec::new(),
+            pending: 0.0,
+            single: 0,
+        }
+    }
+
+    /// Window pixels per simulation unit.
+    pub fn pixels(&self) -> f64 {
+        self.scale * self.zoom
+    }
+
+    pub fn camera(&self) -> Camera {
+        Camera { pixels: self.pixels(), offset: self.offset }
+    }
+
+    pub fn to_world(&self, x: f64, y: f64) -> (f64, f64) {
+        (x / self.pixels() + self.offset.0, y / self.pixels() + self.offset.1)
+    }
+
+    pub fn apply(&mut self, command: Command) {
+        match command {
+            Command::Quit => self.quit = true,
+            Command::TogglePause => self.paused = !self.paused,
+            Command::Step => {
+                self.paused = true;
+                self.single = 1;
+            }
+            Command::StepBack if self.reversible => {
+                self.paused = true;
+                self.single = -1;
+            }
+            Command::Reverse if self.reversible => self.reversed = !self.reversed,
+            Command::First if self.reversible => self.jump = Some(false),
+            Command::Last if self.reversible => self.jump = Some(true),
+            Command::StepBack | Command::Reverse | Command::First | Command::Last => {}
+            Command::Faster => self.speed = (self.speed * 2.0).min(MAX_SPEED),
+            Command::Slower => self.speed = (self.speed / 2.0).max(MIN_SPEED),
+            Command::Zoom { factor, x, y } => {
+                if self.tracking == Tracking::Fit {
+                    self.tracking = Tracking::Off;
+                }
+                let (wx, wy) = self.to_world(x, y);
+                self.zoom *= factor;
+                self.offset = (wx - x / self.pixels(), wy - y / self.pixels());
+            }
+            Command::Pan { dx, dy } => {
+                self.tracking = Tracking::Off;
+                self.offset.0 -= dx / self.pixels();
+                self.offset.1 -= dy / self.pixels();
+            }
+            Command::ResetView => {
+                self.tracking = Tracking::Off;
+                self.zoom = 1.0;
+                self.offset = (0.0, 0.0);
+            }
+            Command::NextColors => self.color_by = self.color_by.next(),
+            Command::ToggleStyle => self.style = match self.style {
+                BodyStyle::Points => BodyStyle::Circles,
+                BodyStyle::Circles => BodyStyle::Points,
+            },
+            Command::ToggleTrails => self.trails = !self.trails,
+            Command::NextVectors => self.vectors = self.vectors.next(),
+            Command::NextTree => self.tree = match self.tree {
+                None => Some(TreeColor::Plain),
+                Some(TreeColor::Plain) => Some(TreeColor::Depth),
+                Some(TreeColor::Depth) => Some(TreeColor::Count),
+                Some(TreeColor::Count) => None,
+            },
+            Command::ToggleOpened => self.opened = !self.opened,
+            Command::ToggleHud => self.hud = !self.hud,
+            Command::NextTracking => self.tracking = self.tracking.next(),
+            Command::SpawnMass { factor } => self.spawn_mass = Some(self.spawn_mass.unwrap_or(1.0) * factor),
+            Command::Select { .. } | Command::Spawn { .. } | Command::Delete { .. } | Command::BoxSelect { .. } => {
+                self.queued.push(command)
+            }
+        }
+    }
+
+    /// Resolves the queued commands against the bodies on screen. Selections and deletions
+    /// take the nearest body within [`PICK_PIXELS`]; any edit clears the selections, as the
+    /// bodies get renumbered.
+    pub fn resolve(&mut self, bodies: &[BodyState]) {
+        let camera = self.camera();
+        let nearest = |x: f64, y: f64| bodies.iter()
+            .map(|b| camera.to_window(b.x, b.y))
+            .map(|(bx, by)| (bx - x).hypot(by - y))
+            .enumerate()
+            .filter(|(_, d)| d.is_finite() && *d <= PICK_PIXELS)
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .map(|(k, _)| k);
+        if self.spawn_mass.is_none() && !bodies.is_empty() {
+            let total = bodies.iter().map(|b| b.m).filter(|m| m.is_finite()).sum::<f64>();
+            self.spawn_mass = Some(if total > 0.0 { total / bodies.len() as f64 } else { 1.0 });
+        }
+        for command in std::mem::replace(&mut self.queued, Vec::new()) {
+            match command {
+                Command::Select { x, y } => self.selected = nearest(x, y),
+                Command::Delete { x, y } => if let Some(k) = nearest(x, y) {
+                    self.edits.push(Edit::Remove(k));
+                },
+                Command::Spawn { x, y, dx, dy } => {
+                    let m = self.spawn_mass.unwrap_or(1.0);
+                    let (wx, wy) = self.to_world(x, y);
+                    let (vx, vy) = (dx / self.pixels() / (SPAWN_STEPS * ALPHA), dy / self.pixels() / (SPAWN_STEPS * ALPHA));
+                    self.edits.push(Edit::Add(BodyState { x: wx, y: wy, vx, vy, m, q: 0.0 }));
+                }
+                Command::BoxSelect { x0, y0, x1, y1 } => {
+                    let (left, right) = (x0.min(x1), x0.max(x1));
+                    let (top, bottom) = (y0.min(y1), y0.max(y1));
+                    self.boxed = bodies.iter()
+                        .map(|b| camera.to_window(b.x, b.y))
+                        .enumerate()
+                        .filter(|(_, (x, y))| *x >= left && *x <= right && *y >= top && *y <= bottom)
+                        .map(|(k, _)| k)
+                        .collect();
+                }
+                _ => {}
+            }
+        }
+        if !self.edits.is_empty() {
+            self.selected = None;
+            self.boxed.clear();
+        }
+        if self.selected.map_or(false, |k| k >= bodies.len()) {
+            self.selected = None;
+        }
+    }
+
+    /// Moves the camera after the bodies in a window of `size` pixels, as [`View::tracking`]
+    /// asks. Following keeps the zoom, so wheel zooming still works around the target.
+    pub fn track(&mut self, bodies: &[BodyState], size: (u32, u32)) {
+        let (width, height) = (size.0 as f64, size.1 as f64);
+        let target = match self.tracking {
+            Tracking::Off => None,
+            Tracking::Center => center_of_mass(bodies),
+            Tracking::Heaviest => bodies.iter()
+                .filter(|b| b.m.is_finite() && b.x.is_finite() && b.y.is_finite())
+                .max_by(|a, b| a.m.partial_cmp(&b.m).unwrap())
+                .map(|b| (b.x, b.y)),
+            Tracking::Selected => self.selected.and_then(|k| bodies.get(k))
+                .filter(|b| b.x.is_finite() && b.y.is_finite())
+                .map(|b| (b.x, b.y)),
+            Tracking::Fit => {
+                let finite = bodies.iter().filter(|b| b.x.is_finite() && b.y.is_finite());
+                let (mut left, mut top) = (std::f64::INFINITY, std::f64::INFINITY);
+                let (mut right, mut bottom) = (std::f64::NEG_INFINITY, std::f64::NEG_INFINITY);
+                for b in finite {
+                    left = left.min(b.x);
+                    right = right.max(b.x);
+                    top = top.min(b.y);
+                    bottom = bottom.max(b.y);
+                }
+                if left > right {
+                    return;
+                }
+                let (w, h) = (right - left, bottom - top);
+                if w > 0.0 || h > 0.0 {
+                    let pixels = (width / w).min(height / h) * FIT_MARGIN;
+                    self.zoom = pixels / self.scale;
+                }
+                Some(((left + right) / 2.0, (top + bottom) / 2.0))
+            }
+        };
+        if let Some((x, y)) = target {
+            self.offset = (x - width / 2.0 / self.pixels(), y - height / 2.0 / self.pixels());
+        }
+    }
+
+    /// Signed number of steps to take before the next frame. Speeds below one step per frame
+    /// are carried over between frames.
+    pub fn advance(&mut self) -> i64 {
+        if self.single != 0 {
+            let single = self.single;
+            self.single = 0;
+            return single;
+        }
+        if self.paused {
+            return 0;
+        }
+        self.pending += self.speed;
+        let steps = self.pending.floor();
+        self.pending -= steps;
+        if self.reversed { -steps as i64 } else { steps as i64 }
+    }
+
+    /// Number of steps a live simulation, which only runs forwards, takes before the next frame.
+    pub fn steps(&mut self) -> usize {
+        self.advance().max(0) as usize
+    }
+}
+
+/// Mass weighted mean position of `bodies`, or the plain mean when they carry no mass. Bodies
+/// with a non-finite mass or position are left out, so that one of them can not lose the camera.
+fn center_of_mass(bodies: &[BodyState]) -> Option<(f64, f64)> {
+    let bodies = bodies.iter()
+        .filter(|b| b.m.is_finite() && b.x.is_finite() && b.y.is_finite())
+        .collect::<Vec<_>>();
+    if bodies.is_empty() {
+        return None;
+    }
+    let mass = bodies.iter().map(|b| b.m).sum::<f64>();
+    let (sum, weight) = if mass > 0.0 {
+        (bodies.iter().fold((0.0, 0.0), |(x, y), b| (x + b.m * b.x, y + b.m * b.y)), mass)
+    } else {
+        (bodies.iter().fold((0.0, 0.0), |(x, y), b| (x + b.x, y + b.y)), bodies.len() as f64)
+    };
+    Some((sum.0 / weight, sum.1 / weight))
+}
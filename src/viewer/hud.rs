@@ -0,0 +1,182 @@
+use std::time::{Duration, Instant};
+
+use mpi::topology::Communicator;
+
+use crate::force::{kinetic_energy, potential_energy};
+use crate::global;
+use crate::initial::BodyState;
+use crate::render::short;
+
+/// Bodies listed one by one in the inspection panel.
+const INSPECT_LISTED: usize = 5;
+
+/// Weight of the newest frame in the moving averages of the phase timings.
+const SMOOTHING: f64 = 0.1;
+
+/// Statistics shown in the on-screen HUD: frame and step rate, milliseconds spent in each
+/// phase, per frame for drawing and per step for the simulation, and the total energy with
+/// its drift since the HUD first measured it.
+pub struct Hud {
+    label: String,
+    frames: usize,
+    since: Instant,
+    fps: f64,
+    /// Steps counted towards `sps` and the totals of the last [`Hud::simulated`] call.
+    steps: u64,
+    sps: f64,
+    totals: (u64, Duration, Duration),
+    /// Averaged milliseconds of every phase, in the order first timed, and what it took in
+    /// the current frame if it was timed.
+    phases: Vec<(&'static str, f64, Option<f64>)>,
+    /// Step and total energy of the last measurement.
+    energy: Option<(u64, f64)>,
+    initial: Option<(usize, f64)>,
+}
+
+impl Hud {
+    pub fn new(label: String) -> Self {
+        Hud {
+            label,
+            frames: 0,
+            since: Instant::now(),
+            fps: 0.0,
+            steps: 0,
+            sps: 0.0,
+            totals: (0, Duration::from_secs(0), Duration::from_secs(0)),
+            phases: Vec::new(),
+            energy: None,
+            initial: None,
+        }
+    }
+
+    /// The engine with its thread or process count, for the first line of the HUD.
+    pub fn engine_label(engine: &str) -> String {
+        match engine {
+            "openmp" | "pthread" => format!("{}, {} threads", engine, *global::THREAD),
+            "mpi_normal" => format!("{}, {} processes", engine, global::WORLD.size()),
+            "mpi_openmp" => format!("{}, {} processes x {} threads", engine, global::WORLD.size(), *global::THREAD),
+            "rayon" | "rayon_tree" => format!("{}, {} threads", engine, rayon::current_num_threads()),
+            _ => format!("{}, 1 thread", engine),
+        }
+    }
+
+    /// Adds `elapsed` to what `phase` took in the current frame.
+    pub fn time(&mut self, phase: &'static str, elapsed: Duration) {
+        let ms = elapsed.as_secs_f64() * 1000.0;
+        match self.phases.iter_mut().find(|x| x.0 == phase) {
+            Some(x) => x.2 = Some(x.2.unwrap_or(0.0) + ms),
+            None => self.phases.push((phase, ms, Some(ms))),
+        }
+    }
+
+    /// Folds one value of `phase` into its average straight away, for phases that do not run
+    /// once per frame.
+    pub fn sample(&mut self, phase: &'static str, ms: f64) {
+        match self.phases.iter_mut().find(|x| x.0 == phase) {
+            Some(x) => x.1 += (ms - x.1) * SMOOTHING,
+            None => self.phases.push((phase, ms, None)),
+        }
+    }
+
+    /// Takes the running totals of a simulation thread and shows the milliseconds per step
+    /// spent stepping and in output since the last call.
+    pub fn simulated(&mut self, steps: u64, stepping: Duration, output: Duration) {
+        let (last, last_stepping, last_output) = self.totals;
+        if steps > last {
+            let count = (steps - last) as f64;
+            self.sample("step", (stepping - last_stepping).as_secs_f64() * 1000.0 / count);
+            self.sample("output", (output - last_output).as_secs_f64() * 1000.0 / count);
+            self.steps += steps - last;
+        }
+        self.totals = (steps, stepping, output);
+    }
+
+    pub fn measure<T, F: FnOnce() -> T>(&mut self, phase: &'static str, f: F) -> T {
+        let start = Instant::now();
+        let result = f();
+        self.time(phase, start.elapsed());
+        result
+    }
+
+    /// Ends a frame: folds its timings into the averages and updates the frame rate once
+    /// per second.
+    pub fn frame(&mut self) {
+        for phase in &mut self.phases {
+            if let Some(ms) = phase.2.take() {
+                phase.1 += (ms - phase.1) * SMOOTHING;
+            }
+        }
+        self.frames += 1;
+        let elapsed = self.since.elapsed();
+        if elapsed >= Duration::from_secs(1) {
+            self.fps = self.frames as f64 / elapsed.as_secs_f64();
+            self.sps = self.steps as f64 / elapsed.as_secs_f64();
+            self.frames = 0;
+            self.steps = 0;
+            self.since = Instant::now();
+        }
+    }
+
+    /// The HUD text. The energy sums over all pairs, so it is only measured with `energy` set
+    /// and when the step changed.
+    pub fn lines(&mut self, step: u64, bodies: &[BodyState], energy: bool) -> Vec<String> {
+        let mut lines = vec![
+            self.label.clone(),
+            format!("step {}  time {:.3}", step, step as f64 * global::ALPHA),
+            format!("fps {:.1}  steps/s {:.1}  bodies {}", self.fps, self.sps, bodies.len()),
+        ];
+        if !self.phases.is_empty() {
+            lines.push(self.phases.iter()
+                .map(|(name, ms, _)| format!("{} {:.1}", name, ms))
+                .collect::<Vec<_>>()
+                .join("  ") + " ms");
+        }
+        if energy {
+            if self.energy.map_or(true, |x| x.0 != step) {
+                let total = kinetic_energy(bodies) + potential_energy(bodies, &**global::FORCE);
+                self.energy = Some((step, total));
+                if self.initial.map_or(true, |x| x.0 != bodies.len()) {
+                    self.initial = Some((bodies.len(), total));
+                }
+            }
+            let total = self.energy.unwrap().1;
+            let initial = self.initial.unwrap().1;
+            lines.push(if total.is_finite() {
+                format!("energy {:.4e}  drift {:+.3}%", total, (total - initial) / initial.abs() * 100.0)
+            } else {
+                "energy unknown for this force".to_string()
+            });
+        }
+        lines
+    }
+}
+
+/// Text of the inspection panel for the bodies at `boxed`: their count, total mass, centre of
+/// mass and mean velocity, then the first few of them.
+pub fn inspect(bodies: &[BodyState], boxed: &[usize]) -> Vec<String> {
+    let boxed = boxed.iter().filter_map(|&k| bodies.get(k).map(|b| (k, b))).collect::<Vec<_>>();
+    if boxed.is_empty() {
+        return Vec::new();
+    }
+    let n = boxed.len() as f64;
+    let mass = boxed.iter().map(|(_, b)| b.m).sum::<f64>();
+    let (cx, cy) = if mass > 0.0 {
+        (boxed.iter().map(|(_, b)| b.m * b.x).sum::<f64>() / mass, boxed.iter().map(|(_, b)| b.m * b.y).sum::<f64>() / mass)
+    } else {
+        (boxed.iter().map(|(_, b)| b.x).sum::<f64>() / n, boxed.iter().map(|(_, b)| b.y).sum::<f64>() / n)
+    };
+    let vx = boxed.iter().map(|(_, b)| b.vx).sum::<f64>() / n;
+    let vy = boxed.iter().map(|(_, b)| b.vy).sum::<f64>() / n;
+    let mut lines = vec![
+        format!("{} bodies  mass {}", boxed.len(), short(mass)),
+        format!("centre ({}, {})", short(cx), short(cy)),
+        format!("mean velocity ({}, {})", short(vx), short(vy)),
+    ];
+    for (k, b) in boxed.iter().take(INSPECT_LISTED) {
+        lines.push(format!("#{} m {} at ({}, {}) v ({}, {})", k, short(b.m), short(b.x), short(b.y), short(b.vx), short(b.vy)));
+    }
+    if boxed.len() > INSPECT_LISTED {
+        lines.push(format!("and {} more", boxed.len() - INSPECT_LISTED));
+    }
+    lines
+}
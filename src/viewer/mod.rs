@@ -0,0 +1,211 @@
+pub use control::*;
+pub use hud::*;
+pub use runner::*;
+pub use sdl::*;
+pub use terminal::*;
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::global;
+use crate::initial::BodyState;
+use crate::io;
+use crate::quad_tree::node::TreeSnapshot;
+use crate::render::{Motion, Vectors};
+
+mod control;
+mod hud;
+mod runner;
+mod sdl;
+mod terminal;
+
+/// What the viewer needs from an engine.
+pub trait Simulation {
+    /// Advances every body by one step.
+    fn step(&mut self);
+
+    /// Runs once on the thread that steps the engine, before the first step, for per-thread
+    /// settings such as the OpenMP thread count.
+    fn prepare(&mut self) {}
+
+    /// The current state of the bodies, for drawing and recording.
+    fn bodies(&self) -> Vec<BodyState>;
+
+    /// The acceleration of every body that the next step kicks it with, for engines that carry
+    /// it over from the last step. Checkpoints save it, so that a restart continues bit for bit.
+    fn accelerations(&self) -> Option<Vec<(f64, f64)>> {
+        None
+    }
+
+    /// The thread or MPI rank that updates each body, for engines that split the bodies
+    /// statically.
+    fn owners(&self) -> Option<Vec<usize>> {
+        None
+    }
+
+    /// The quad tree of the Barnes-Hut engines, with the nodes body `selected` opens.
+    fn tree(&self, _selected: Option<usize>) -> Option<TreeSnapshot> {
+        None
+    }
+
+    /// Adds a dynamic body between two steps.
+    fn add(&mut self, _body: BodyState) -> Result<(), String> {
+        Err("this engine can not add bodies at runtime".to_string())
+    }
+
+    /// Removes the dynamic body at `index` of [`Simulation::bodies`] between two steps.
+    fn remove(&mut self, _index: usize) -> Result<(), String> {
+        Err("this engine can not remove bodies at runtime".to_string())
+    }
+}
+
+/// Where the display mode draws.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Backend {
+    Sdl,
+    Terminal,
+}
+
+/// A window the viewer draws into and takes input from.
+pub trait Frontend {
+    /// Drawable size in the pixels that [`View`] coordinates refer to.
+    fn size(&self) -> (u32, u32);
+
+    fn poll(&mut self) -> Vec<Command>;
+
+    fn draw(&mut self, view: &View, scene: &Scene);
+
+    /// Shortest time between two frames.
+    fn frame_time(&self) -> Option<Duration> {
+        *global::FRAME_TIME
+    }
+}
+
+/// Opens the `--display` front end for a domain of `width × height` window pixels, with a
+/// view whose `scale` fits that domain into it.
+pub fn open(title: &str, width: f64, height: f64, scale: f64) -> (Box<dyn Frontend>, View) {
+    let frontend: Box<dyn Frontend> = match *global::DISPLAY {
+        Backend::Sdl => Box::new(SdlFrontend::with_size(title, width as u32, height as u32)),
+        Backend::Terminal => Box::new(TerminalFrontend::new()),
+    };
+    let (w, h) = frontend.size();
+    let fit = (w as f64 / width).min(h as f64 / height);
+    (frontend, View::new(scale * fit))
+}
+
+/// Sleeps for what is left of `frame` since `begin`.
+pub fn pace(begin: Instant, frame: Option<Duration>) {
+    if let Some(rest) = frame.and_then(|x| x.checked_sub(begin.elapsed())) {
+        std::thread::sleep(rest);
+    }
+}
+
+/// Everything a front end draws in one frame.
+pub struct Scene<'a> {
+    pub bodies: &'a [BodyState],
+    pub owners: Option<&'a [usize]>,
+    pub motion: &'a Motion,
+    pub tree: Option<&'a TreeSnapshot>,
+    pub hud: Option<&'a [String]>,
+    /// Summary of the box selected bodies.
+    pub inspect: &'a [String],
+}
+
+/// Runs `sim` in the window titled `title`, or for `--steps` steps without one in benchmark
+/// and record mode. Recording and checkpoints happen here, after every step. The window runs
+/// the engine on a thread of its own, see [`Runner`].
+pub fn start<S: Simulation + Send + 'static>(title: &str, mut sim: S) {
+    if let Err(e) = io::record(global::step(), || sim.bodies()) {
+        fail(&e);
+    }
+    if *global::HEADLESS {
+        sim.prepare();
+        let start = std::time::SystemTime::now();
+        for _ in 0..*global::STEPS {
+            sim.step();
+            if let Err(e) = output(global::advance(), &sim) {
+                fail(&e);
+            }
+        }
+        let end = std::time::SystemTime::now();
+        println!("Duration: {} ms", end.duration_since(start).unwrap().as_millis());
+    } else {
+        display(title, sim);
+    }
+}
+
+fn output<S: Simulation>(step: u64, sim: &S) -> Result<(), String> {
+    io::record(step, || sim.bodies())?;
+    io::checkpoint(step, || (sim.bodies(), sim.accelerations()))
+}
+
+/// Reports a failed output and exits; the display loop first gives its front end back.
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
+
+/// Trail length when `T` turns on trails that `--trails` left off.
+pub const DEFAULT_TRAILS: usize = 64;
+
+fn display<S: Simulation + Send + 'static>(title: &str, mut sim: S) {
+    let runner = Arc::new(Runner::new(*global::STEPPING));
+    let worker = {
+        let runner = runner.clone();
+        std::thread::spawn(move || runner.run(&mut sim))
+    };
+    let (mut frontend, mut view) = open(title, *global::WIDTH, *global::HEIGHT, *global::SCALE_FACTOR);
+    let mut motion = Motion::new(if *global::TRAILS > 0 { *global::TRAILS } else { DEFAULT_TRAILS });
+    let engine = global::MATCHES.as_ref().and_then(|m| m.value_of("engine")).unwrap_or_default();
+    let mut hud = Hud::new(Hud::engine_label(engine));
+    let mut snapshot: Option<Snapshot> = None;
+    let mut n = 0;
+    let mut start = std::time::SystemTime::now();
+    while !view.quit && !runner.failed() {
+        let begin = Instant::now();
+        n += 1;
+        if let Some(next) = runner.take() {
+            hud.simulated(next.steps, next.stepping, next.output);
+            snapshot = Some(next);
+        }
+        if let Some(s) = &snapshot {
+            view.track(&s.bodies, frontend.size());
+            observe(&view, &mut motion, s.step, &s.bodies);
+            let lines = if view.hud { Some(hud.lines(s.step, &s.bodies, true)) } else { None };
+            let boxed = inspect(&s.bodies, &view.boxed);
+            hud.measure("draw", || frontend.draw(&view, &Scene {
+                bodies: &s.bodies,
+                owners: s.owners.as_ref().map(|x| x.as_slice()),
+                motion: &motion,
+                tree: s.tree.as_ref(),
+                hud: lines.as_ref().map(|x| x.as_slice()),
+                inspect: &boxed,
+            }));
+        }
+        for command in frontend.poll() {
+            view.apply(command);
+        }
+        if let Some(s) = &snapshot {
+            view.resolve(&s.bodies);
+        }
+        runner.request(&mut view);
+        hud.frame();
+        global::show_fps(&mut n, &mut start);
+        pace(begin, frontend.frame_time());
+    }
+    runner.quit();
+    let result = worker.join().expect("the simulation thread panicked");
+    drop(frontend);
+    if let Err(e) = result {
+        fail(&e);
+    }
+}
+
+/// Feeds `motion` only while the view shows trails or arrows, so hidden trails cost nothing.
+pub fn observe(view: &View, motion: &mut Motion, step: u64, bodies: &[BodyState]) {
+    if view.trails || view.vectors != Vectors::None {
+        motion.observe(step, bodies);
+    } else {
+        motion.clear();
+    }
+}
@@ -0,0 +1,209 @@
+use std::time::{Duration, Instant};
+
+use parking_lot::{Condvar, Mutex};
+
+use crate::global;
+use crate::initial::BodyState;
+use crate::quad_tree::node::TreeSnapshot;
+use crate::viewer::{Edit, output, Simulation, View};
+
+/// How the simulation thread paces itself against the window.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Stepping {
+    /// Runs the steps the view asks for once per displayed frame, like the old lock-step
+    /// loops, but without holding up the window while it works.
+    Frame,
+    /// Steps as fast as possible and publishes whenever the window took the last snapshot.
+    Free,
+}
+
+/// The engine at one step, as published by the simulation thread.
+pub struct Snapshot {
+    pub step: u64,
+    pub bodies: Vec<BodyState>,
+    pub owners: Option<Vec<usize>>,
+    pub tree: Option<TreeSnapshot>,
+    /// Steps taken since the thread started and the time they spent stepping and in output.
+    pub steps: u64,
+    pub stepping: Duration,
+    pub output: Duration,
+}
+
+/// The simulation thread fills a snapshot of its own and swaps it in here, the window takes
+/// the newest one, so neither ever waits for the other to finish a frame or a step.
+pub struct DoubleBuffer<T> {
+    slot: Mutex<Option<T>>,
+}
+
+impl<T> Default for DoubleBuffer<T> {
+    fn default() -> Self {
+        DoubleBuffer { slot: Mutex::new(None) }
+    }
+}
+
+impl<T> DoubleBuffer<T> {
+    /// Replaces the snapshot the window has not taken yet, if any.
+    pub fn publish(&self, value: T) {
+        *self.slot.lock() = Some(value);
+    }
+
+    pub fn take(&self) -> Option<T> {
+        self.slot.lock().take()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slot.lock().is_none()
+    }
+}
+
+/// What the window asks of the simulation thread.
+#[derive(Default)]
+struct Requests {
+    quit: bool,
+    paused: bool,
+    /// Steps left in the current batch.
+    budget: u64,
+    tree: bool,
+    selected: Option<usize>,
+    edits: Vec<Edit>,
+    /// Set by the simulation thread when it stopped on an output error.
+    failed: bool,
+}
+
+/// Connects the window to the simulation thread: requests go one way under a mutex, with a
+/// condition variable to wake the idle thread, snapshots come back through a [`DoubleBuffer`].
+pub struct Runner {
+    stepping: Stepping,
+    requests: Mutex<Requests>,
+    wake: Condvar,
+    snapshots: DoubleBuffer<Snapshot>,
+}
+
+impl Runner {
+    pub fn new(stepping: Stepping) -> Self {
+        Runner { stepping, requests: Mutex::new(Requests::default()), wake: Condvar::new(), snapshots: DoubleBuffer::default() }
+    }
+
+    pub fn take(&self) -> Option<Snapshot> {
+        self.snapshots.take()
+    }
+
+    /// Passes the wishes of `view` on. With [`Stepping::Frame`] a new batch of steps is only
+    /// handed out once the last one is done, so a slow engine runs at most the requested
+    /// steps per frame instead of piling them up.
+    pub fn request(&self, view: &mut View) {
+        let mut requests = self.requests.lock();
+        requests.paused = view.paused;
+        requests.tree = view.tree.is_some();
+        requests.selected = if view.opened { view.selected } else { None };
+        requests.edits.append(&mut view.edits);
+        match self.stepping {
+            Stepping::Frame if requests.budget == 0 => requests.budget = view.steps() as u64,
+            Stepping::Free if view.paused => requests.budget += view.steps() as u64,
+            _ => {}
+        }
+        self.wake.notify_one();
+    }
+
+    /// Whether the simulation thread stopped on an error, which [`Runner::run`] returns.
+    pub fn failed(&self) -> bool {
+        self.requests.lock().failed
+    }
+
+    pub fn quit(&self) {
+        self.requests.lock().quit = true;
+        self.wake.notify_one();
+    }
+
+    /// The loop of the simulation thread. It sleeps while there is nothing to do and
+    /// publishes after every batch, or without stepping when the window wants another tree.
+    /// It stops on the first output error and returns it, so that the window can give the
+    /// terminal back before reporting it.
+    pub fn run<S: Simulation>(&self, sim: &mut S) -> Result<(), String> {
+        sim.prepare();
+        let mut published = None;
+        let (mut steps, mut stepping, mut writing) = (0, Duration::from_secs(0), Duration::from_secs(0));
+        loop {
+            let (advance, free, wants, edits) = {
+                let mut requests = self.requests.lock();
+                loop {
+                    let free = self.stepping == Stepping::Free && !requests.paused;
+                    let wants = (requests.tree, requests.selected);
+                    if requests.quit {
+                        return Ok(());
+                    }
+                    if requests.budget > 0 || free || published != Some(wants) || !requests.edits.is_empty() {
+                        let edits = std::mem::replace(&mut requests.edits, Vec::new());
+                        break (requests.budget > 0 || free, free, wants, edits);
+                    }
+                    self.wake.wait(&mut requests);
+                }
+            };
+            if !edits.is_empty() {
+                apply(sim, edits);
+                published = None;
+            }
+            if advance {
+                let start = Instant::now();
+                sim.step();
+                let step = global::advance();
+                let middle = Instant::now();
+                if let Err(e) = output(step, sim) {
+                    self.requests.lock().failed = true;
+                    return Err(e);
+                }
+                steps += 1;
+                stepping += middle - start;
+                writing += middle.elapsed();
+                let mut requests = self.requests.lock();
+                requests.budget = requests.budget.saturating_sub(1);
+                let batch = requests.budget > 0;
+                let taken = self.snapshots.is_empty();
+                drop(requests);
+                // running freely, a snapshot the window has not taken yet is not worth replacing
+                if batch || (free && !taken) {
+                    continue;
+                }
+            }
+            self.snapshots.publish(Snapshot {
+                step: global::step(),
+                bodies: sim.bodies(),
+                owners: sim.owners(),
+                tree: if wants.0 { sim.tree(wants.1) } else { None },
+                steps,
+                stepping,
+                output: writing,
+            });
+            published = Some(wants);
+        }
+    }
+}
+
+/// Applies the edits of one frame, all indexed by the same snapshot: removals from the back so
+/// that they do not shift each other, then additions.
+fn apply<S: Simulation>(sim: &mut S, mut edits: Vec<Edit>) {
+    if global::TRAJECTORY.is_some() {
+        eprintln!("bodies can not be added or removed while recording a trajectory");
+        return;
+    }
+    edits.sort_by_key(|x| match x {
+        Edit::Remove(k) => std::cmp::Reverse(Some(*k)),
+        Edit::Add(_) => std::cmp::Reverse(None),
+    });
+    edits.dedup_by(|a, b| match (a, b) {
+        (Edit::Remove(x), Edit::Remove(y)) => x == y,
+        _ => false,
+    });
+    // a window that clicks faster than snapshots arrive may point past the end
+    let count = sim.bodies().len();
+    for edit in edits {
+        let result = match edit {
+            Edit::Add(body) => sim.add(body),
+            Edit::Remove(k) if k < count => sim.remove(k),
+            Edit::Remove(_) => Ok(()),
+        };
+        if let Err(e) = result {
+            eprintln!("{}", e);
+        }
+    }
+}
@@ -0,0 +1,239 @@
+use sdl2::event::Event;
+use sdl2::EventPump;
+use sdl2::keyboard::Keycode;
+use sdl2::mouse::MouseButton;
+use sdl2::pixels::Color;
+use sdl2::rect::{Point, Rect};
+use sdl2::render::Canvas;
+use sdl2::video::Window;
+
+use crate::render::{draw_bodies, draw_legend, draw_panel, draw_tree, outline, panel_size, short, Surface};
+use crate::viewer::{Command, Frontend, MASS_STEP, Scene, View, ZOOM_STEP};
+
+/// What a drag with the left button does, decided by the modifiers when it starts.
+enum Drag {
+    Pan(i32, i32),
+    Spawn(i32, i32),
+    Box(i32, i32),
+}
+
+/// The SDL window of the display mode.
+///
+/// Space pauses, `S` or the right arrow steps once, `+` and `-` change the speed, the wheel
+/// zooms, dragging pans and `0` resets the view. `C` switches the colour map and `B` the
+/// body style, `T` shows trails and `V` switches between velocity and acceleration arrows.
+/// Clicking without dragging selects a body, `Q` switches the quad tree overlay and `O` the
+/// highlighting of the nodes the selected body opens. `H` shows the HUD and `F` switches what
+/// the camera follows, which panning or resetting the view turns off.
+///
+/// Dragging with Ctrl spawns a body moving along the drag, the wheel setting its mass while
+/// the button is down; a right click deletes the nearest body and dragging with Shift boxes
+/// bodies in for inspection. Replays also step back with the left arrow, reverse with `R` and
+/// jump to either end with Home and End.
+pub struct SdlFrontend {
+    canvas: Canvas<Window>,
+    event_pump: EventPump,
+    drag: Option<Drag>,
+    /// Where the left button went down, to tell clicks from drags.
+    pressed: Option<(i32, i32)>,
+    mouse: (i32, i32),
+    ctrl: bool,
+    shift: bool,
+    frame: u64,
+}
+
+impl SdlFrontend {
+    pub fn with_size(title: &str, width: u32, height: u32) -> Self {
+        let sdl_context = sdl2::init().unwrap();
+        let video_subsystem = sdl_context.video().unwrap();
+        let window = video_subsystem.window(title, width, height)
+            .position_centered()
+            .build()
+            .unwrap();
+        let mut canvas = window.into_canvas().build().unwrap();
+        canvas.set_draw_color(Color::RGB(0, 255, 255));
+        canvas.clear();
+        canvas.present();
+        let event_pump = sdl_context.event_pump().unwrap();
+        SdlFrontend {
+            canvas,
+            event_pump,
+            drag: None,
+            pressed: None,
+            mouse: (0, 0),
+            ctrl: false,
+            shift: false,
+            frame: 0,
+        }
+    }
+}
+
+impl Frontend for SdlFrontend {
+    /// Size of the window in pixels.
+    fn size(&self) -> (u32, u32) {
+        Surface::size(&self.canvas)
+    }
+
+    fn poll(&mut self) -> Vec<Command> {
+        let mut commands = Vec::new();
+        for event in self.event_pump.poll_iter() {
+            match event {
+                Event::Quit { .. } => commands.push(Command::Quit),
+                Event::KeyDown { keycode: Some(key), .. } => match key {
+                    Keycode::Escape => commands.push(Command::Quit),
+                    Keycode::Space => commands.push(Command::TogglePause),
+                    Keycode::S | Keycode::Right => commands.push(Command::Step),
+                    Keycode::Left => commands.push(Command::StepBack),
+                    Keycode::R => commands.push(Command::Reverse),
+                    Keycode::Home => commands.push(Command::First),
+                    Keycode::End => commands.push(Command::Last),
+                    Keycode::Plus | Keycode::Equals | Keycode::KpPlus => commands.push(Command::Faster),
+                    Keycode::Minus | Keycode::KpMinus => commands.push(Command::Slower),
+                    Keycode::Num0 | Keycode::Kp0 => commands.push(Command::ResetView),
+                    Keycode::C => commands.push(Command::NextColors),
+                    Keycode::B => commands.push(Command::ToggleStyle),
+                    Keycode::T => commands.push(Command::ToggleTrails),
+                    Keycode::V => commands.push(Command::NextVectors),
+                    Keycode::Q => commands.push(Command::NextTree),
+                    Keycode::O => commands.push(Command::ToggleOpened),
+                    Keycode::H => commands.push(Command::ToggleHud),
+                    Keycode::F => commands.push(Command::NextTracking),
+                    Keycode::LCtrl | Keycode::RCtrl => self.ctrl = true,
+                    Keycode::LShift | Keycode::RShift => self.shift = true,
+                    _ => {}
+                },
+                Event::KeyUp { keycode: Some(key), .. } => match key {
+                    Keycode::LCtrl | Keycode::RCtrl => self.ctrl = false,
+                    Keycode::LShift | Keycode::RShift => self.shift = false,
+                    _ => {}
+                },
+                Event::MouseWheel { y, .. } if y != 0 => match self.drag {
+                    Some(Drag::Spawn(..)) => commands.push(Command::SpawnMass { factor: MASS_STEP.powi(y) }),
+                    _ => commands.push(Command::Zoom {
+                        factor: ZOOM_STEP.powi(y),
+                        x: self.mouse.0 as f64,
+                        y: self.mouse.1 as f64,
+                    }),
+                },
+                Event::MouseButtonDown { mouse_btn: MouseButton::Left, x, y, .. } => {
+                    self.drag = Some(if self.ctrl {
+                        Drag::Spawn(x, y)
+                    } else if self.shift {
+                        Drag::Box(x, y)
+                    } else {
+                        Drag::Pan(x, y)
+                    });
+                    self.pressed = Some((x, y));
+                }
+                Event::MouseButtonUp { mouse_btn: MouseButton::Left, x, y, .. } => {
+                    let pressed = self.pressed.take();
+                    match self.drag.take() {
+                        Some(Drag::Spawn(px, py)) => commands.push(Command::Spawn {
+                            x: px as f64,
+                            y: py as f64,
+                            dx: (x - px) as f64,
+                            dy: (y - py) as f64,
+                        }),
+                        Some(Drag::Box(px, py)) => commands.push(Command::BoxSelect {
+                            x0: px as f64,
+                            y0: py as f64,
+                            x1: x as f64,
+                            y1: y as f64,
+                        }),
+                        _ => if let Some((px, py)) = pressed {
+                            if (x - px).abs() <= 3 && (y - py).abs() <= 3 {
+                                commands.push(Command::Select { x: x as f64, y: y as f64 });
+                            }
+                        },
+                    }
+                }
+                Event::MouseButtonDown { mouse_btn: MouseButton::Right, x, y, .. } => {
+                    commands.push(Command::Delete { x: x as f64, y: y as f64 });
+                }
+                Event::MouseMotion { x, y, .. } => {
+                    self.mouse = (x, y);
+                    if let Some(Drag::Pan(px, py)) = self.drag {
+                        commands.push(Command::Pan { dx: (x - px) as f64, dy: (y - py) as f64 });
+                        self.drag = Some(Drag::Pan(x, y));
+                    }
+                }
+                _ => {}
+            }
+        }
+        commands
+    }
+
+    /// Draws the bodies in the colours and style of `view`, with the legend of the colour map
+    /// and whichever trails, arrows and quad tree `view` asks for, and previews the body or
+    /// box being dragged out.
+    fn draw(&mut self, view: &View, scene: &Scene) {
+        self.canvas.set_draw_color(Color::RGB(255, 255, 255));
+        self.canvas.clear();
+        self.frame += 1;
+        let bodies = scene.bodies;
+        let coloring = view.color_by.colors(bodies, scene.owners, self.frame);
+        let camera = view.camera();
+        if view.trails {
+            scene.motion.draw_trails(&mut self.canvas, &camera, &coloring.colors);
+        }
+        if let (Some(color), Some(tree)) = (view.tree, scene.tree) {
+            draw_tree(&mut self.canvas, &camera, tree, color, view.opened);
+        }
+        draw_bodies(&mut self.canvas, &camera, bodies, &coloring.colors, view.style);
+        scene.motion.draw_vectors(&mut self.canvas, &camera, bodies, view.vectors);
+        self.canvas.set_color((0, 0, 220));
+        for b in view.boxed.iter().filter_map(|&k| bodies.get(k)) {
+            let (x, y) = camera.to_window(b.x, b.y);
+            Surface::draw_lines(&mut self.canvas, &outline(x as i32 - 4, y as i32 - 4, x as i32 + 4, y as i32 + 4));
+        }
+        if let Some(b) = view.selected.and_then(|k| bodies.get(k)) {
+            let (x, y) = camera.to_window(b.x, b.y);
+            let (x, y) = (x as i32, y as i32);
+            self.canvas.set_color((220, 0, 0));
+            Surface::draw_lines(&mut self.canvas, &outline(x - 6, y - 6, x + 6, y + 6));
+        }
+        match self.drag {
+            Some(Drag::Spawn(x, y)) => {
+                let (mx, my) = self.mouse;
+                self.canvas.set_color((220, 0, 0));
+                Surface::draw_lines(&mut self.canvas, &[(x, y, mx, my)]);
+                let mass = view.spawn_mass.map_or("mean".to_string(), short);
+                draw_panel(&mut self.canvas, mx + 10, my + 10, &[format!("mass {}", mass)]);
+            }
+            Some(Drag::Box(x, y)) => {
+                self.canvas.set_color((0, 0, 220));
+                Surface::draw_lines(&mut self.canvas, &outline(x, y, self.mouse.0, self.mouse.1));
+            }
+            _ => {}
+        }
+        draw_legend(&mut self.canvas, view.color_by, &coloring);
+        if let Some(lines) = scene.hud {
+            draw_panel(&mut self.canvas, 10, 10, lines);
+        }
+        let (_, height) = panel_size(scene.inspect);
+        let (_, window) = Surface::size(&self.canvas);
+        draw_panel(&mut self.canvas, 10, window as i32 - height - 10, scene.inspect);
+        self.canvas.present();
+    }
+}
+
+impl Surface for Canvas<Window> {
+    fn size(&self) -> (u32, u32) {
+        self.output_size().expect("unable to read the window size")
+    }
+
+    fn set_color(&mut self, (r, g, b): (u8, u8, u8)) {
+        self.set_draw_color(Color::RGB(r, g, b));
+    }
+
+    fn fill_rects(&mut self, rects: &[(i32, i32, u32, u32)]) {
+        let rects = rects.iter().map(|&(x, y, w, h)| Rect::new(x, y, w, h)).collect::<Vec<_>>();
+        Canvas::fill_rects(self, &rects).expect("unable to draw rectangles");
+    }
+
+    fn draw_lines(&mut self, lines: &[(i32, i32, i32, i32)]) {
+        for &(x0, y0, x1, y1) in lines {
+            self.draw_line(Point::new(x0, y0), Point::new(x1, y1)).expect("unable to draw lines");
+        }
+    }
+}
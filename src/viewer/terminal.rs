@@ -0,0 +1,292 @@
+use std::io::{Read, Write};
+use std::process::{Command as Process, Stdio};
+use std::sync::mpsc::{channel, Receiver};
+use std::time::{Duration, Instant};
+
+use crate::global;
+use crate::render::{Braille, category, ColorBy, draw_tree, Legend, outline, ramp, short, Surface};
+use crate::viewer::{Command, Frontend, Scene, View, ZOOM_STEP};
+
+/// Highest frame rate of the terminal, which redraws every cell of the screen each frame.
+pub const TERMINAL_FPS: f64 = 20.0;
+/// Frames between two checks of the terminal size.
+const RESIZE_FRAMES: u64 = 20;
+/// How long an unfinished escape sequence waits for the rest of it, as a slow connection may
+/// split one over two reads; a lone Esc still there afterwards was the Esc key.
+const ESCAPE_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// A terminal cell: its character, its colour, and whether it belongs to a text panel.
+type Cell = (char, Option<(u8, u8, u8)>, bool);
+
+/// The terminal front end of the display mode, drawing the bodies as braille dots in ANSI
+/// colours. With the cycling colour map the cells are shaded by how many bodies they hold.
+///
+/// The keys are those of the window where the terminal has them, Esc or Ctrl-C quitting.
+/// There is no mouse, so `z` and `x` zoom on the middle of the screen, `i`, `j`, `k` and `l`
+/// or the up and down arrows pan, and nothing can be selected or edited.
+pub struct TerminalFrontend {
+    input: Receiver<u8>,
+    /// The unfinished escape sequence the last poll ended on, and since when it waits.
+    pending: Vec<u8>,
+    since: Option<Instant>,
+    /// Terminal settings to restore, as printed by `stty -g`.
+    saved: Option<String>,
+    columns: usize,
+    rows: usize,
+    frame: u64,
+}
+
+impl TerminalFrontend {
+    pub fn new() -> Self {
+        let saved = stty(&["-g"]).map(|x| x.trim().to_string());
+        if stty(&["raw", "-echo"]).is_none() {
+            eprintln!("unable to put the terminal in raw mode, input needs Enter");
+        }
+        let (sender, input) = channel();
+        std::thread::spawn(move || {
+            let mut buffer = [0; 16];
+            let mut stdin = std::io::stdin();
+            while let Ok(n) = stdin.read(&mut buffer) {
+                if n == 0 {
+                    break;
+                }
+                for &x in &buffer[..n] {
+                    if sender.send(x).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        // alternate screen, hidden cursor
+        print!("\x1b[?1049h\x1b[?25l\x1b[2J");
+        let (columns, rows) = terminal_size();
+        TerminalFrontend { input, pending: Vec::new(), since: None, saved, columns, rows, frame: 0 }
+    }
+
+    fn pan(&self, dx: f64, dy: f64) -> Command {
+        let (width, height) = self.size();
+        Command::Pan { dx: dx * width as f64 / 4.0, dy: dy * height as f64 / 4.0 }
+    }
+
+    fn zoom(&self, factor: f64) -> Command {
+        let (width, height) = self.size();
+        Command::Zoom { factor, x: width as f64 / 2.0, y: height as f64 / 2.0 }
+    }
+}
+
+impl Drop for TerminalFrontend {
+    fn drop(&mut self) {
+        print!("\x1b[0m\x1b[?25h\x1b[?1049l");
+        let _ = std::io::stdout().flush();
+        if let Some(saved) = &self.saved {
+            stty(&[saved.as_str()]);
+        }
+    }
+}
+
+impl Frontend for TerminalFrontend {
+    /// Braille dots, two across and four down per cell.
+    fn size(&self) -> (u32, u32) {
+        (self.columns as u32 * 2, self.rows as u32 * 4)
+    }
+
+    fn poll(&mut self) -> Vec<Command> {
+        let mut bytes = std::mem::replace(&mut self.pending, Vec::new());
+        // bytes held back for longer than the timeout are decoded as they are
+        let stale = if self.since.map_or(false, |x| x.elapsed() >= ESCAPE_TIMEOUT) { bytes.len() } else { 0 };
+        bytes.extend(self.input.try_iter());
+        let mut commands = Vec::new();
+        let mut k = 0;
+        while k < bytes.len() {
+            if k >= stale && unfinished(&bytes[k..]) {
+                self.pending = bytes[k..].to_vec();
+                break;
+            }
+            if bytes[k] == 0x1b && bytes.get(k + 1) == Some(&b'[') {
+                let code = bytes.get(k + 2).cloned();
+                k += 3;
+                if code.map_or(false, |x| x.is_ascii_digit()) && bytes.get(k) == Some(&b'~') {
+                    k += 1;
+                }
+                match code {
+                    Some(b'A') => commands.push(self.pan(0.0, 1.0)),
+                    Some(b'B') => commands.push(self.pan(0.0, -1.0)),
+                    Some(b'C') => commands.push(Command::Step),
+                    Some(b'D') => commands.push(Command::StepBack),
+                    Some(b'H') | Some(b'1') => commands.push(Command::First),
+                    Some(b'F') | Some(b'4') => commands.push(Command::Last),
+                    _ => {}
+                }
+                continue;
+            }
+            match bytes[k] {
+                0x1b | 0x03 => commands.push(Command::Quit),
+                b' ' => commands.push(Command::TogglePause),
+                b's' => commands.push(Command::Step),
+                b'r' => commands.push(Command::Reverse),
+                b'+' | b'=' => commands.push(Command::Faster),
+                b'-' => commands.push(Command::Slower),
+                b'0' => commands.push(Command::ResetView),
+                b'c' => commands.push(Command::NextColors),
+                b't' => commands.push(Command::ToggleTrails),
+                b'v' => commands.push(Command::NextVectors),
+                b'q' => commands.push(Command::NextTree),
+                b'o' => commands.push(Command::ToggleOpened),
+                b'h' => commands.push(Command::ToggleHud),
+                b'f' => commands.push(Command::NextTracking),
+                b'z' => commands.push(self.zoom(ZOOM_STEP)),
+                b'x' => commands.push(self.zoom(1.0 / ZOOM_STEP)),
+                b'i' => commands.push(self.pan(0.0, 1.0)),
+                b'k' => commands.push(self.pan(0.0, -1.0)),
+                b'j' => commands.push(self.pan(1.0, 0.0)),
+                b'l' => commands.push(self.pan(-1.0, 0.0)),
+                _ => {}
+            }
+            k += 1;
+        }
+        if self.pending.is_empty() || stale > 0 {
+            self.since = None;
+        }
+        if !self.pending.is_empty() && self.since.is_none() {
+            self.since = Some(Instant::now());
+        }
+        commands
+    }
+
+    /// Draws the bodies, whichever trails, arrows and quad tree `view` asks for, and the legend,
+    /// HUD and inspection text on dark panels.
+    fn draw(&mut self, view: &View, scene: &Scene) {
+        if self.frame % RESIZE_FRAMES == 0 {
+            let size = terminal_size();
+            if size != (self.columns, self.rows) {
+                self.columns = size.0;
+                self.rows = size.1;
+                print!("\x1b[2J");
+            }
+        }
+        self.frame += 1;
+        let bodies = scene.bodies;
+        let coloring = view.color_by.colors(bodies, scene.owners, self.frame);
+        let camera = view.camera();
+        let mut braille = Braille::new(self.columns, self.rows);
+        if view.trails {
+            scene.motion.draw_trails(&mut braille, &camera, &coloring.colors);
+        }
+        if let (Some(color), Some(tree)) = (view.tree, scene.tree) {
+            draw_tree(&mut braille, &camera, tree, color, view.opened);
+        }
+        scene.motion.draw_vectors(&mut braille, &camera, bodies, view.vectors);
+        for (b, &color) in bodies.iter().zip(&coloring.colors) {
+            let (x, y) = camera.to_window(b.x, b.y);
+            braille.plot(x, y, color);
+        }
+        if let Some(b) = view.selected.and_then(|k| bodies.get(k)) {
+            let (x, y) = camera.to_window(b.x, b.y);
+            braille.set_color((220, 0, 0));
+            braille.draw_lines(&outline(x as i32 - 3, y as i32 - 3, x as i32 + 3, y as i32 + 3));
+        }
+        let density = view.color_by == ColorBy::Cycle;
+        let mut cells = braille.cells(density).into_iter().map(|(c, color)| (c, color, false)).collect::<Vec<Cell>>();
+
+        let legend = match coloring.legend {
+            _ if density => {
+                let mut legend = text("density ");
+                legend.extend((0..8).map(|k| ('█', Some(ramp(k as f64 / 7.0)), true)));
+                legend
+            }
+            Legend::None => Vec::new(),
+            Legend::Range(min, max) => {
+                let mut legend = text(&format!("{} {} ", view.color_by.name(), short(min)));
+                legend.extend((0..8).map(|k| ('█', Some(ramp(k as f64 / 7.0)), true)));
+                legend.extend(text(&format!(" {}", short(max))));
+                legend
+            }
+            Legend::Owners(count) => {
+                let mut legend = text("owner ");
+                legend.extend((0..count.min(16)).map(|k| ('█', Some(category(k)), true)));
+                legend
+            }
+        };
+        stamp(&mut cells, self.columns, 0, self.columns.saturating_sub(legend.len()), &legend);
+        for (row, line) in scene.hud.unwrap_or(&[]).iter().enumerate() {
+            stamp(&mut cells, self.columns, row, 0, &text(line));
+        }
+        let first = self.rows.saturating_sub(scene.inspect.len());
+        for (row, line) in scene.inspect.iter().enumerate() {
+            stamp(&mut cells, self.columns, first + row, 0, &text(line));
+        }
+
+        let mut out = String::with_capacity(cells.len() * 8);
+        out.push_str("\x1b[H");
+        let mut style = None;
+        for (k, &(c, color, panel)) in cells.iter().enumerate() {
+            if k > 0 && k % self.columns == 0 {
+                out.push_str("\x1b[0m\r\n");
+                style = None;
+            }
+            if style != Some((color, panel)) {
+                out.push_str("\x1b[0m");
+                if panel {
+                    out.push_str("\x1b[40;97m");
+                }
+                if let Some((r, g, b)) = color {
+                    out.push_str(&format!("\x1b[38;2;{};{};{}m", r, g, b));
+                }
+                style = Some((color, panel));
+            }
+            out.push(c);
+        }
+        out.push_str("\x1b[0m");
+        let mut stdout = std::io::stdout();
+        let _ = stdout.write_all(out.as_bytes()).and_then(|_| stdout.flush());
+    }
+
+    fn frame_time(&self) -> Option<Duration> {
+        let cap = Duration::from_secs_f64(1.0 / TERMINAL_FPS);
+        Some(global::FRAME_TIME.map_or(cap, |x| x.max(cap)))
+    }
+}
+
+/// Whether `bytes` is the start of an escape sequence that the keys above decode, cut short.
+fn unfinished(bytes: &[u8]) -> bool {
+    match bytes {
+        [0x1b] | [0x1b, b'['] => true,
+        [0x1b, b'[', code] => code.is_ascii_digit(),
+        _ => false,
+    }
+}
+
+/// Plain text on a panel, one cell per character.
+fn text(line: &str) -> Vec<Cell> {
+    line.chars().map(|c| (c, None, true)).collect()
+}
+
+/// Writes `line` into the cells from `column` of `row` on, clipped to the screen.
+fn stamp(cells: &mut [Cell], columns: usize, row: usize, column: usize, line: &[Cell]) {
+    if row * columns >= cells.len() {
+        return;
+    }
+    for (k, &cell) in line.iter().take(columns.saturating_sub(column)).enumerate() {
+        cells[row * columns + column + k] = cell;
+    }
+}
+
+/// Runs `stty` on the terminal and returns what it printed, or `None` if it failed.
+fn stty(args: &[&str]) -> Option<String> {
+    let output = Process::new("stty").args(args).stdin(Stdio::inherit()).output().ok()?;
+    if output.status.success() { String::from_utf8(output.stdout).ok() } else { None }
+}
+
+/// Columns and rows of the terminal, 80 × 24 if `stty` can not tell.
+fn terminal_size() -> (usize, usize) {
+    let size = stty(&["size"]).and_then(|x| {
+        let mut numbers = x.split_whitespace().filter_map(|x| x.parse::<usize>().ok());
+        let rows = numbers.next()?;
+        let columns = numbers.next()?;
+        Some((columns, rows))
+    });
+    match size {
+        Some((columns, rows)) if columns > 0 && rows > 0 => (columns, rows),
+        _ => (80, 24),
+    }
+}